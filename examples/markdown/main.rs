@@ -13,6 +13,7 @@ use tera::Context;
 
 pub const TEMPLATE_DIR: &str = "$CARGO_MANIFEST_DIR/src/templates";
 
+#[derive(Debug)]
 pub struct Slugifier;
 
 impl TextFilter for Slugifier {
@@ -33,7 +34,7 @@ pub fn build(tpl_dir: &Path, recipe_dir: &Path, output_dir: &Path) -> Result<()>
     context.insert("title", "Sweet Potator Example Document");
     engine.forced_context = Some(context);
     let generator = Generator::new(engine, vec!["jpg".into()], "md".into(), Slugifier);
-    Ok(generator.generate(recipe_dir, output_dir)?)
+    generator.generate(recipe_dir, output_dir)
 }
 
 fn main() {
@@ -1,17 +1,31 @@
-use std::fs;
+use std::{fs, path::PathBuf};
 
-use sweet_potator::{generator::Generator, template};
+use notify::RecursiveMode;
+use sweet_potator::{
+    generator::Generator,
+    recipe::{cost::Prices, synonyms::Synonyms},
+    template,
+};
 use tera::Context;
 
 use crate::{
     AppInfo,
-    config::Config,
+    config::{Config, TemplateKind},
     error::{Error, Result},
     options,
-    terminal::color::Colorize,
+    terminal::{color::Colorize, message::write},
+    watch,
 };
 
 pub fn build(config: &Config, options: &options::Build) -> Result<()> {
+    generate(config, options)?;
+    if options.watch {
+        watch_and_generate(config, options, || {})?;
+    }
+    Ok(())
+}
+
+pub(super) fn generate(config: &Config, options: &options::Build) -> Result<()> {
     if !options.output_dir.exists() {
         fs::create_dir_all(&options.output_dir)?;
     }
@@ -20,11 +34,7 @@ pub fn build(config: &Config, options: &options::Build) -> Result<()> {
         .templates
         .get(tpl_name)
         .ok_or_else(|| Error::TemplateNameNotConfigured(tpl_name.yellow()))?;
-    let tpl_dir = if let Some(dir) = &options.template_dir {
-        dir.join(tpl_name)
-    } else {
-        config.template_dir().join(tpl_name)
-    };
+    let tpl_dir = template_dir(config, options);
     if !tpl_dir.exists() {
         return Err(Error::TemplateNameNotFound(options.template_name.yellow()));
     }
@@ -34,15 +44,79 @@ pub fn build(config: &Config, options: &options::Build) -> Result<()> {
         &tpl_options.extension,
         Some(&tpl_options.language),
     )?;
+    engine.set_inline_markdown(tpl_options.inline_markdown);
     let mut context = Context::new();
     context.insert("app", &AppInfo::default());
     context.insert("lf", "\n");
     engine.forced_context = Some(context);
-    let generator = Generator::new(
+    let mut generator = Generator::new(
         engine,
         config.image_file_exts.iter().map(Into::into).collect(),
         tpl_options.extension.as_str().into(),
         tpl_options.file_name_filter,
     );
-    Ok(generator.generate(&config.recipe_dir, &options.output_dir)?)
+    generator.clean = options.clean;
+    generator.layout = tpl_options.layout.clone();
+    generator.image_options = tpl_options.images.clone();
+    generator.site_url.clone_from(&tpl_options.site_url);
+    generator.site_name.clone_from(&tpl_options.site_name);
+    generator.feed.clone_from(&tpl_options.feed);
+    generator.expand_references = tpl_options.expand_references;
+    generator.synonyms = Synonyms::load(&config.dir)?;
+    generator.prices = Prices::load(&config.dir, &generator.synonyms)?;
+    match tpl_options.kind {
+        TemplateKind::Site => generator.generate(&config.recipe_dir, &options.output_dir)?,
+        TemplateKind::Cookbook => {
+            generator.generate_cookbook(&config.recipe_dir, &options.output_dir, &options.tags)?;
+        }
+        TemplateKind::Epub => generator.generate_epub(
+            &config.recipe_dir,
+            &options.output_dir,
+            &options.tags,
+            &tpl_options.metadata,
+        )?,
+    }
+    Ok(())
+}
+
+/// Generates the output again on every change of recipes, template or config.
+///
+/// Rebuilds are never clean, `options.clean` only applies to the initial
+/// build. `on_generate` is called after every successful generation.
+pub(super) fn watch_and_generate<F: FnMut()>(
+    config: &Config,
+    options: &options::Build,
+    mut on_generate: F,
+) -> Result<()> {
+    let recipe_dir = config.recipe_dir.clone();
+    let tpl_dir = template_dir(config, options);
+    let paths = [
+        (config.dir.as_path(), RecursiveMode::NonRecursive),
+        (recipe_dir.as_path(), RecursiveMode::Recursive),
+        (tpl_dir.as_path(), RecursiveMode::Recursive),
+    ];
+    let options = &options::Build {
+        clean: false,
+        ..options.clone()
+    };
+    let mut config = config.reload()?;
+    write::info("watching for changes...")?;
+    watch::watch(&paths, &options.output_dir, || {
+        config = config.reload()?;
+        generate(&config, options)?;
+        on_generate();
+        write::success(format!(
+            "rebuilt '{}'",
+            options.output_dir.to_string_lossy().yellow()
+        ))?;
+        Ok(())
+    })
+}
+
+fn template_dir(config: &Config, options: &options::Build) -> PathBuf {
+    if let Some(dir) = &options.template_dir {
+        dir.join(&options.template_name)
+    } else {
+        config.template_dir().join(&options.template_name)
+    }
 }
@@ -0,0 +1,34 @@
+use sweet_potator::recipe::{matching::can_make as find, pantry::Pantry, synonyms::Synonyms};
+
+use crate::{
+    config::Config,
+    error::Result,
+    options,
+    terminal::{color::Colorize, message::write, writeln},
+};
+
+use super::cook_with::load_recipes;
+
+pub fn can_make(config: &Config, options: &options::CanMake) -> Result<()> {
+    let (recipes, result) = load_recipes(&config.recipe_dir)?;
+    let synonyms = Synonyms::load(&config.dir)?;
+    let pantry = Pantry::load(&config.dir)?;
+    let coverages = find(&recipes, &pantry, options.missing, &synonyms);
+    if coverages.is_empty() {
+        write::info("no recipe can be made with the pantry")?;
+    }
+    for coverage in coverages {
+        if coverage.missing.is_empty() {
+            writeln(&coverage.recipe.title)?;
+        } else {
+            let missing: Vec<_> = coverage.missing.iter().map(ToString::to_string).collect();
+            writeln(format!(
+                "{} {} {}",
+                coverage.recipe.title,
+                "·".green(),
+                missing.join("; ").red()
+            ))?;
+        }
+    }
+    result
+}
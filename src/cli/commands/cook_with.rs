@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use sweet_potator::recipe::{Recipe, directory::Directory, matching::rank, synonyms::Synonyms};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    options,
+    terminal::{color::Colorize, message::write, writeln},
+};
+
+pub fn cook_with(config: &Config, options: &options::CookWith) -> Result<()> {
+    let (recipes, result) = load_recipes(&config.recipe_dir)?;
+    let synonyms = Synonyms::load(&config.dir)?;
+    let matches = rank(&recipes, &options.ingredients, &synonyms);
+    if matches.is_empty() {
+        write::info("no recipe uses any of these ingredients")?;
+    }
+    for recipe_match in matches.iter().take(options.limit.unwrap_or(usize::MAX)) {
+        let mut line = format!(
+            "{} {} {}",
+            recipe_match.recipe.title,
+            "·".green(),
+            recipe_match.used.join(", ").green()
+        );
+        if !recipe_match.others.is_empty() {
+            line.push_str(&format!(" + {}", recipe_match.others.join(", ")));
+        }
+        writeln(line)?;
+    }
+    result
+}
+
+/// Loads all recipes of `recipe_dir`, writing errors of corrupted recipes.
+///
+/// The result is an error if any recipe could not be loaded.
+pub(super) fn load_recipes(recipe_dir: &Path) -> Result<(Vec<Recipe>, Result<()>)> {
+    let mut result = Ok(());
+    let mut recipes = Vec::new();
+    for directory in Directory::list_all(recipe_dir)? {
+        match directory.load() {
+            Ok(recipe) => recipes.push(recipe),
+            Err(error) => {
+                write::error(error)?;
+                result = Err(Error::CorruptedRecipeList);
+            }
+        }
+    }
+    Ok((recipes, result))
+}
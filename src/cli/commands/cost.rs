@@ -0,0 +1,64 @@
+use std::io;
+
+use sweet_potator::{
+    error::Error as SweetPotatorError,
+    recipe::{
+        cost::{PRICES_FILE_NAME, Prices},
+        directory::Directory,
+        synonyms::Synonyms,
+    },
+};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    options,
+    terminal::{color::Colorize, writeln},
+};
+
+pub fn cost(config: &Config, options: &options::Cost) -> Result<()> {
+    let directory = Directory::from_title(&config.recipe_dir, &options.title)?;
+    let recipe = directory.load().map_err(|error| match error {
+        SweetPotatorError::Io(error) if error.kind() == io::ErrorKind::NotFound => {
+            let path = directory.recipe_path();
+            let file_name = path.file_name().unwrap();
+            Error::RecipeFileNotFound(file_name.to_string_lossy().yellow())
+        }
+        _ => error.into(),
+    })?;
+    let synonyms = Synonyms::load(&config.dir)?;
+    let prices = Prices::load(&config.dir, &synonyms)?.ok_or_else(|| {
+        Error::PricesFileNotFound(config.dir.join(PRICES_FILE_NAME).to_string_lossy().yellow())
+    })?;
+    let cost = prices.estimate(&recipe, &synonyms);
+    let amount = |value: f64| {
+        format!("{value:.2} {}", cost.currency)
+            .trim_end()
+            .to_string()
+    };
+    let mut mappings = vec![("Total".to_string(), amount(cost.total))];
+    if let Some(per_serving) = cost.per_serving {
+        mappings.push(("Per serving".into(), amount(per_serving)));
+    }
+    if let Some(servings) = options.servings {
+        let yields = &recipe.metadata.yields;
+        if cost.per_serving.is_none() {
+            return Err(Error::ServingsUnknown(
+                recipe.title.yellow(),
+                yields.to_string(),
+            ));
+        }
+        let total = cost.total * f64::from(servings) / f64::from(yields.value);
+        mappings.push((format!("{servings} servings"), amount(total)));
+    }
+    if !cost.unpriced.is_empty() {
+        mappings.push(("Unpriced".into(), cost.unpriced.join(", ").red()));
+    }
+    let width = mappings
+        .iter()
+        .fold(0, |acc, (name, _)| name.chars().count().max(acc));
+    for (name, value) in mappings {
+        writeln(format!("{name:>width$} {} {value}", "·".green()))?;
+    }
+    Ok(())
+}
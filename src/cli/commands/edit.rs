@@ -14,6 +14,10 @@ use crate::{
 pub fn edit(config: &Config, options: &options::Edit) -> Result<()> {
     if options.image_path.is_some() {
         set_image(config, options)
+    } else if options.add_image_path.is_some() {
+        add_image(config, options)
+    } else if options.remove_image_name.is_some() {
+        remove_image(config, options)
     } else {
         open_recipe(config, options)
     }
@@ -37,7 +41,7 @@ pub fn open_recipe(config: &Config, options: &options::Edit) -> Result<()> {
     )?;
     editor::open(&config.editor, &directory.recipe_path())?;
     let recipe = directory.load()?;
-    if title.map_or(true, |title| title != recipe.title) {
+    if title.is_none_or(|title| title != recipe.title) {
         directory.update_from_title(&recipe.title)?;
     }
     write::success(format!("edited recipe '{}'", recipe.title.yellow()))?;
@@ -60,3 +64,29 @@ pub fn set_image(config: &Config, options: &options::Edit) -> Result<()> {
     ))?;
     Ok(())
 }
+
+pub fn add_image(config: &Config, options: &options::Edit) -> Result<()> {
+    let directory = Directory::from_title(&config.recipe_dir, &options.title)?;
+    if let Some(path) = &options.add_image_path {
+        let file_exts = os_str_vec(&config.image_file_exts);
+        directory.add_gallery_image(path, &file_exts)?;
+    }
+    write::success(format!(
+        "added image to gallery of recipe directory '{}'",
+        directory
+            .base_name()
+            .to_str()
+            .expect("invalid directory name")
+            .yellow()
+    ))?;
+    Ok(())
+}
+
+pub fn remove_image(config: &Config, options: &options::Edit) -> Result<()> {
+    let directory = Directory::from_title(&config.recipe_dir, &options.title)?;
+    if let Some(name) = &options.remove_image_name {
+        directory.remove_gallery_image(name.as_ref())?;
+        write::success(format!("removed image '{}' from gallery", name.yellow()))?;
+    }
+    Ok(())
+}
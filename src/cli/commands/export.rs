@@ -5,17 +5,26 @@ use std::{
 };
 
 use serde::Serialize;
-use sweet_potator::recipe::{Recipe, directory::Directory};
+use slug::slugify;
+use sweet_potator::{
+    pdf::Renderer,
+    recipe::{
+        cost::{CostedRecipe, Prices},
+        directory::Directory,
+        synonyms::Synonyms,
+    },
+};
 
 use crate::{
     config::Config,
     error::{Error, Result},
-    options,
+    options::{self, ExportFormat},
     terminal::{color::Colorize, message::write},
 };
 
 const RECIPE_JSON_FILE_NAME: &str = "recipes.json";
 const RECIPE_IMAGE_DIR: &str = "images";
+const COOKBOOK_FILE_NAME: &str = "cookbook.pdf";
 
 struct Image(PathBuf);
 
@@ -36,20 +45,36 @@ impl Serialize for Image {
 
 #[derive(Serialize)]
 struct Entry {
-    recipe: Recipe,
+    /// With the estimated cost, if there is a price list
+    recipe: CostedRecipe,
     image: Option<Image>,
 }
 
 pub fn export(config: &Config, options: &options::Export) -> Result<()> {
+    match options.format {
+        ExportFormat::Json => export_json(config, options),
+        ExportFormat::Pdf => export_pdf(config, options),
+    }
+}
+
+fn export_json(config: &Config, options: &options::Export) -> Result<()> {
     let image_file_exts: Vec<OsString> = config.image_file_exts.iter().map(Into::into).collect();
+    let synonyms = Synonyms::load(&config.dir)?;
+    let prices = Prices::load(&config.dir, &synonyms)?;
     let entries: Vec<_> = Directory::list_all(&config.recipe_dir)?
         .iter()
         .map(|directory| {
             let recipe = directory.load()?;
+            let cost = prices
+                .as_ref()
+                .map(|prices| prices.estimate(&recipe, &synonyms));
             let image = directory
                 .image_file_name(&image_file_exts)?
                 .map(|name| Image(Path::new(directory.base_name()).join(name)));
-            Ok(Entry { recipe, image })
+            Ok(Entry {
+                recipe: CostedRecipe { recipe, cost },
+                image,
+            })
         })
         .collect::<Result<_>>()?;
     if options.output_dir.exists() {
@@ -82,3 +107,57 @@ pub fn export(config: &Config, options: &options::Export) -> Result<()> {
     ))?;
     Ok(())
 }
+
+fn export_pdf(config: &Config, options: &options::Export) -> Result<()> {
+    let image_file_exts: Vec<OsString> = config.image_file_exts.iter().map(Into::into).collect();
+    let mut entries = Vec::new();
+    for directory in Directory::list_all(&config.recipe_dir)? {
+        let recipe = directory.load()?;
+        if !options.tags.is_empty()
+            && !recipe
+                .metadata
+                .tags
+                .iter()
+                .any(|tag| options.tags.contains(tag))
+        {
+            continue;
+        }
+        let image = directory
+            .image_file_name(&image_file_exts)?
+            .map(|name| directory.path().join(name));
+        let name = match &recipe.metadata.slug {
+            Some(slug) => slugify(slug),
+            None => slugify(directory.base_name().to_string_lossy()),
+        };
+        entries.push((name, recipe, image));
+    }
+    entries.sort_by_cached_key(|(_, recipe, _)| recipe.title.to_lowercase());
+    if options.output_dir.exists() {
+        return Err(Error::OutputDirectoryAlreadyExists(
+            options.output_dir.to_string_lossy().yellow(),
+        ));
+    }
+    fs::create_dir_all(&options.output_dir)?;
+    let renderer = Renderer::new(config.pdf.clone())?;
+    if options.cookbook {
+        let recipes: Vec<_> = entries
+            .into_iter()
+            .map(|(_, recipe, image)| (recipe, image))
+            .collect();
+        let path = options.output_dir.join(COOKBOOK_FILE_NAME);
+        fs::write(&path, renderer.render_cookbook(&recipes)?)?;
+        write::success(format!(
+            "{} recipes exported to {}",
+            recipes.len(),
+            path.to_string_lossy()
+        ))?;
+    } else {
+        for (name, recipe, image) in &entries {
+            let path = options.output_dir.join(name).with_extension("pdf");
+            write::info(format!("render '{}'", path.to_string_lossy().yellow()))?;
+            fs::write(path, renderer.render_recipe(recipe, image.as_deref())?)?;
+        }
+        write::success(format!("{} recipes exported", entries.len()))?;
+    }
+    Ok(())
+}
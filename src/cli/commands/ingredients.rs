@@ -0,0 +1,31 @@
+use sweet_potator::recipe::synonyms::Synonyms;
+
+use crate::{
+    config::Config,
+    error::Result,
+    options::{self, IngredientsSubCommand},
+    terminal::{message::write, writeln},
+};
+
+use super::cook_with::load_recipes;
+
+pub fn ingredients(config: &Config, options: &options::Ingredients) -> Result<()> {
+    let (recipes, result) = load_recipes(&config.recipe_dir)?;
+    let synonyms = Synonyms::load(&config.dir)?;
+    match options.subcommand {
+        IngredientsSubCommand::Unmatched(_) => {
+            let names = recipes
+                .iter()
+                .flat_map(|recipe| recipe.ingredients.iter())
+                .map(|ingredient| ingredient.name.as_str());
+            let unmatched = synonyms.unmatched(names);
+            if unmatched.is_empty() {
+                write::success("all ingredients have a canonical name")?;
+            }
+            for name in unmatched {
+                writeln(name)?;
+            }
+        }
+    }
+    result
+}
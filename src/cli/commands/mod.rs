@@ -1,15 +1,27 @@
 mod build;
+mod can_make;
+mod cook_with;
+mod cost;
 mod create;
 mod delete;
 mod edit;
 mod export;
 mod info;
+mod ingredients;
 mod list;
+mod pantry;
+mod serve;
 
 pub use build::build;
+pub use can_make::can_make;
+pub use cook_with::cook_with;
+pub use cost::cost;
 pub use create::create;
 pub use delete::delete;
 pub use edit::edit;
 pub use export::export;
 pub use info::info;
+pub use ingredients::ingredients;
 pub use list::list;
+pub use pantry::pantry;
+pub use serve::serve;
@@ -0,0 +1,62 @@
+use sweet_potator::recipe::{
+    ParseFromStr, ingredient::Ingredient, pantry::Pantry, synonyms::Synonyms,
+};
+
+use crate::{
+    config::Config,
+    error::{Error, Result},
+    options::{self, PantrySubCommand},
+    terminal::{color::Colorize, message::write, writeln},
+};
+
+pub fn pantry(config: &Config, options: &options::Pantry) -> Result<()> {
+    let synonyms = Synonyms::load(&config.dir)?;
+    let mut pantry = Pantry::load(&config.dir)?;
+    match &options.subcommand {
+        PantrySubCommand::Add(options) => {
+            for item in &options.items {
+                if options.staple {
+                    pantry.staples.push(item.trim().into());
+                } else {
+                    pantry.add(parse_item(item)?, &synonyms);
+                }
+                write::success(format!("added '{}' to the pantry", item.trim().yellow()))?;
+            }
+            pantry.store(&config.dir)?;
+        }
+        PantrySubCommand::List(_) => {
+            for item in pantry.items() {
+                writeln(item.to_string())?;
+            }
+            for staple in &pantry.staples {
+                writeln(format!("{} {}", staple, "(staple)".bright_black()))?;
+            }
+        }
+        PantrySubCommand::Remove(options) => {
+            for item in &options.items {
+                let is_removed = if options.staple {
+                    let id = synonyms.id(item);
+                    let count = pantry.staples.len();
+                    pantry.staples.retain(|staple| synonyms.id(staple) != id);
+                    pantry.staples.len() < count
+                } else {
+                    pantry.remove(&parse_item(item)?, &synonyms)
+                };
+                if !is_removed {
+                    return Err(Error::PantryItemNotFound(item.trim().yellow()));
+                }
+                write::success(format!(
+                    "removed '{}' from the pantry",
+                    item.trim().yellow()
+                ))?;
+            }
+            pantry.store(&config.dir)?;
+        }
+    }
+    Ok(())
+}
+
+fn parse_item(item: &str) -> Result<Ingredient> {
+    Ingredient::parse_from_str(item.trim())
+        .map_err(|error| sweet_potator::error::Error::InvalidPantryItem(item.into(), error).into())
+}
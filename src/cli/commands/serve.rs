@@ -0,0 +1,43 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    thread,
+};
+
+use crate::{
+    config::Config,
+    error::Result,
+    options,
+    server,
+    terminal::{color::Colorize, message::write},
+};
+
+use super::build::{generate, watch_and_generate};
+
+pub fn serve(config: &Config, options: &options::Serve) -> Result<()> {
+    let build_options = &options::Build {
+        clean: options.clean,
+        template_name: options.template_name.clone(),
+        template_dir: options.template_dir.clone(),
+        tags: options.tags.clone(),
+        watch: true,
+        output_dir: options.output_dir.clone(),
+    };
+    generate(config, build_options)?;
+    // bind before watching, the scope would wait for the watcher forever
+    let server = server::bind(options.port)?;
+    let version = AtomicU64::new(0);
+    thread::scope(|scope| {
+        let watcher = scope.spawn(|| {
+            watch_and_generate(config, build_options, || {
+                version.fetch_add(1, Ordering::Relaxed);
+            })
+        });
+        write::info(format!(
+            "serving '{}' at {}",
+            build_options.output_dir.to_string_lossy().yellow(),
+            format!("http://localhost:{}", options.port).yellow()
+        ))?;
+        server::serve(&server, &build_options.output_dir, &version)?;
+        watcher.join().expect("watcher thread panicked")
+    })
+}
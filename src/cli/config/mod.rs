@@ -8,7 +8,8 @@ use dirs::home_dir;
 use serde::Deserialize;
 use slug::slugify;
 use sweet_potator::{
-    generator::{self},
+    generator::{self, epub, feed::FeedOptions, image::ImageOptions, layout::Layout},
+    pdf::PdfOptions,
     util::sanitize_file_name,
 };
 
@@ -26,19 +27,14 @@ const TEMPLATE_DIR: &str = "templates";
 
 const TRUE: fn() -> bool = || true;
 
-#[derive(Clone, Copy, Deserialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum FileNameFilter {
+    #[default]
     Sanitize,
     Slugify,
 }
 
-impl Default for FileNameFilter {
-    fn default() -> Self {
-        Self::Sanitize
-    }
-}
-
 impl generator::TextFilter for FileNameFilter {
     fn filter<S: AsRef<str>>(&self, text: S) -> String {
         match self {
@@ -48,15 +44,49 @@ impl generator::TextFilter for FileNameFilter {
     }
 }
 
+/// What a template renders.
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    /// A page per recipe with index, tag and additional pages
+    #[default]
+    Site,
+    /// A single document with all recipes
+    Cookbook,
+    /// An EPUB file with a chapter per recipe
+    Epub,
+}
+
 #[derive(Deserialize)]
 pub struct GeneratorOptions {
+    #[serde(default)]
+    pub kind: TemplateKind,
     #[serde(default = "TRUE")]
     pub escape: bool,
+    /// Render inline Markdown of instructions and notes (HTML templates)
+    #[serde(default)]
+    pub inline_markdown: bool,
+    /// Include the ingredients of referenced recipes in shopping lists
+    #[serde(default)]
+    pub expand_references: bool,
     pub extension: String,
     #[serde(default)]
     pub file_name_filter: FileNameFilter,
     #[serde(default = "DEFAULT_LANGUAGE")]
     pub language: String,
+    #[serde(flatten)]
+    pub layout: Layout,
+    #[serde(default)]
+    pub images: ImageOptions,
+    /// Book metadata (EPUB templates only)
+    #[serde(default)]
+    pub metadata: epub::Metadata,
+    /// Absolute URL of the output directory when published
+    pub site_url: Option<String>,
+    /// Name of the site for link previews
+    pub site_name: Option<String>,
+    /// Atom feed of recent recipes (site templates with `site_url` only)
+    pub feed: Option<FeedOptions>,
 }
 
 #[derive(Deserialize)]
@@ -68,6 +98,8 @@ pub struct Config {
     #[serde(rename = "image_file_extensions")]
     pub image_file_exts: Vec<String>,
     pub templates: HashMap<String, GeneratorOptions>,
+    #[serde(default)]
+    pub pdf: PdfOptions,
 }
 
 impl Config {
@@ -94,6 +126,13 @@ impl Config {
         Ok(config)
     }
 
+    /// Loads the config file again, but keeps the current recipe directory.
+    pub fn reload(&self) -> std::result::Result<Self, ConfigError> {
+        let mut config = Self::load(&self.dir.join(CONFIG_FILE_NAME))?;
+        config.recipe_dir.clone_from(&self.recipe_dir);
+        Ok(config)
+    }
+
     pub fn default_recipe_path(&self) -> PathBuf {
         self.dir.join(DEFAULT_RECIPE_FILE_NAME)
     }
@@ -14,10 +14,18 @@ pub enum Error {
     Io(#[from] io::Error),
     #[error("output directory '{0}' already exists")]
     OutputDirectoryAlreadyExists(String),
+    #[error("'{0}' is not in the pantry")]
+    PantryItemNotFound(String),
+    #[error("prices file '{0}' not found")]
+    PricesFileNotFound(String),
     #[error("recipe directory '{0}' not found")]
     RecipeDirNotFound(String),
     #[error("recipe file '{0}' not found")]
     RecipeFileNotFound(String),
+    #[error("cannot start server: {0}")]
+    Server(String),
+    #[error("servings of '{0}' unknown, the recipe yields {1}")]
+    ServingsUnknown(String, String),
     #[error(transparent)]
     SweetPotator(sweet_potator::error::Error),
     #[error("template name '{0}' not configured")]
@@ -26,6 +34,8 @@ pub enum Error {
     TemplateNameNotFound(String),
     #[error("template engine error: {0}")]
     Tera(#[from] tera::Error),
+    #[error("file watcher error: {0}")]
+    Watch(#[from] notify::Error),
 }
 
 impl From<sweet_potator::error::Error> for Error {
@@ -5,8 +5,10 @@ mod config;
 mod editor;
 mod error;
 mod options;
+mod server;
 mod terminal;
 mod util;
+mod watch;
 
 use std::fs;
 
@@ -62,16 +64,25 @@ impl Default for AppInfo {
 }
 
 fn route(config: &Config, options: Options) -> Result<()> {
-    use SubCommand::{Build, Create, Delete, Edit, Export, Info, List};
+    use SubCommand::{
+        Build, CanMake, CookWith, Cost, Create, Delete, Edit, Export, Info, Ingredients, List,
+        Pantry, Serve,
+    };
 
     match options.subcommand {
         Build(options) => commands::build(config, &options),
+        CanMake(options) => commands::can_make(config, &options),
+        CookWith(options) => commands::cook_with(config, &options),
+        Cost(options) => commands::cost(config, &options),
         Create(options) => commands::create(config, &options),
         Delete(options) => commands::delete(config, &options),
         Edit(options) => commands::edit(config, &options),
         Export(options) => commands::export(config, &options),
         Info(_) => commands::info(config),
+        Ingredients(options) => commands::ingredients(config, &options),
         List(options) => commands::list(config, &options),
+        Pantry(options) => commands::pantry(config, &options),
+        Serve(options) => commands::serve(config, &options),
     }
 }
 
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser)]
 #[clap(version, about)]
@@ -20,18 +20,30 @@ pub struct Options {
 #[derive(Parser)]
 pub enum SubCommand {
     Build(Build),
+    #[clap(name = "can-make")]
+    CanMake(CanMake),
+    #[clap(name = "cook-with")]
+    CookWith(CookWith),
+    Cost(Cost),
     #[clap(name = "new")]
     Create(Create),
     Delete(Delete),
     Edit(Edit),
     Export(Export),
     Info(Info),
+    Ingredients(Ingredients),
     List(List),
+    Pantry(Pantry),
+    Serve(Serve),
 }
 
 /// Build recipe page
-#[derive(Default, Parser)]
+#[derive(Clone, Default, Parser)]
 pub struct Build {
+    /// Render all recipes, even if they did not change since the last build
+    #[clap(long)]
+    pub clean: bool,
+
     /// Template name to use
     #[clap(long = "template", value_name = "NAME", default_value = "html")]
     pub template_name: String,
@@ -40,10 +52,49 @@ pub struct Build {
     #[clap(long, value_name = "DIR")]
     pub template_dir: Option<PathBuf>,
 
+    /// Only include recipes with one of these tags (cookbook and EPUB templates only)
+    #[clap(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Rebuild on changes of recipes, template or config
+    #[clap(long)]
+    pub watch: bool,
+
     /// Output (build) directory
     pub output_dir: PathBuf,
 }
 
+/// List recipes that can be made with the ingredients of the pantry
+#[derive(Default, Parser)]
+pub struct CanMake {
+    /// Also list recipes missing up to this number of ingredients
+    #[clap(long, value_name = "N", default_value = "0")]
+    pub missing: usize,
+}
+
+/// List recipes using the given ingredients, best matches first
+#[derive(Default, Parser)]
+pub struct CookWith {
+    /// Maximum number of recipes to list
+    #[clap(long)]
+    pub limit: Option<usize>,
+
+    /// Available ingredients
+    #[clap(required = true)]
+    pub ingredients: Vec<String>,
+}
+
+/// Estimate the cost of a recipe from the price list
+#[derive(Default, Parser)]
+pub struct Cost {
+    /// Also estimate the cost of this number of servings
+    #[clap(long, value_name = "N")]
+    pub servings: Option<u32>,
+
+    /// Recipe title
+    pub title: String,
+}
+
 /// Create new recipe
 #[derive(Default, Parser)]
 pub struct Create {
@@ -69,17 +120,62 @@ pub struct Edit {
     #[clap(long = "set-image", value_name = "FILE")]
     pub image_path: Option<PathBuf>,
 
+    /// Add an image to the recipe gallery
+    #[clap(long = "add-image", value_name = "FILE")]
+    pub add_image_path: Option<PathBuf>,
+
+    /// Remove an image from the recipe gallery
+    #[clap(long = "remove-image", value_name = "NAME")]
+    pub remove_image_name: Option<String>,
+
     /// Recipe title
     pub title: String,
 }
 
-/// Export recipes as JSON file
+/// Export recipes as JSON or PDF files
 #[derive(Default, Parser)]
 pub struct Export {
+    /// Output format
+    #[clap(long = "to", value_enum, default_value = "json")]
+    pub format: ExportFormat,
+
+    /// Export a single PDF file with all recipes (PDF only)
+    #[clap(long)]
+    pub cookbook: bool,
+
+    /// Only export recipes with one of these tags (PDF only)
+    #[clap(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
     /// Output directory
     pub output_dir: PathBuf,
 }
 
+#[derive(Clone, Copy, Default, ValueEnum)]
+pub enum ExportFormat {
+    /// A JSON file with all recipes and their images
+    #[default]
+    Json,
+    /// A PDF file per recipe (or a cookbook)
+    Pdf,
+}
+
+/// Inspect the ingredients of all recipes
+#[derive(Parser)]
+pub struct Ingredients {
+    #[clap(subcommand)]
+    pub subcommand: IngredientsSubCommand,
+}
+
+#[derive(Parser)]
+pub enum IngredientsSubCommand {
+    Unmatched(IngredientsUnmatched),
+}
+
+/// List ingredient names without a canonical name in the synonyms file
+#[derive(Default, Parser)]
+pub struct IngredientsUnmatched;
+
 /// List recipes
 #[derive(Default, Parser)]
 pub struct List {
@@ -92,6 +188,75 @@ pub struct List {
     pub tags: Option<Vec<String>>,
 }
 
+/// Manage the ingredients on hand
+#[derive(Parser)]
+pub struct Pantry {
+    #[clap(subcommand)]
+    pub subcommand: PantrySubCommand,
+}
+
+#[derive(Parser)]
+pub enum PantrySubCommand {
+    Add(PantryAdd),
+    List(PantryList),
+    Remove(PantryRemove),
+}
+
+/// Add ingredients to the pantry, e.g. "flour: 1 kg" or "tomatoes, canned"
+#[derive(Default, Parser)]
+pub struct PantryAdd {
+    /// Add always available ingredients (staples) like salt
+    #[clap(long)]
+    pub staple: bool,
+
+    /// Ingredients with optional kind and quantity
+    #[clap(required = true)]
+    pub items: Vec<String>,
+}
+
+/// List the ingredients of the pantry
+#[derive(Default, Parser)]
+pub struct PantryList;
+
+/// Remove ingredients from the pantry (all kinds unless a kind is given)
+#[derive(Default, Parser)]
+pub struct PantryRemove {
+    /// Remove staples
+    #[clap(long)]
+    pub staple: bool,
+
+    /// Ingredients with optional kind
+    #[clap(required = true)]
+    pub items: Vec<String>,
+}
+
+/// Build and serve recipe page locally, rebuild and reload pages on changes
+#[derive(Default, Parser)]
+pub struct Serve {
+    /// Port to listen on (localhost only)
+    #[clap(long, default_value = "8000")]
+    pub port: u16,
+
+    /// Render all recipes of the initial build, even if they did not change since the last build
+    #[clap(long)]
+    pub clean: bool,
+
+    /// Template name to use
+    #[clap(long = "template", value_name = "NAME", default_value = "html")]
+    pub template_name: String,
+
+    /// Use a custom template directory
+    #[clap(long, value_name = "DIR")]
+    pub template_dir: Option<PathBuf>,
+
+    /// Only include recipes with one of these tags (cookbook and EPUB templates only)
+    #[clap(long = "tag", value_name = "TAG")]
+    pub tags: Vec<String>,
+
+    /// Output (build) directory
+    pub output_dir: PathBuf,
+}
+
 /// Show application info
 #[derive(Default, Parser)]
 pub struct Info;
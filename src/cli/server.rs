@@ -0,0 +1,150 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    error::{Error, Result},
+    terminal::message::write,
+};
+
+const LIVE_RELOAD_PATH: &str = "/__live-reload";
+
+const LIVE_RELOAD_SCRIPT: &str = concat!(
+    "<script>(() => {\n",
+    "  let version = null;\n",
+    "  setInterval(async () => {\n",
+    "    try {\n",
+    "      const current = await (await fetch(\"/__live-reload\")).text();\n",
+    "      if (version !== null && current !== version) location.reload();\n",
+    "      version = current;\n",
+    "    } catch {}\n",
+    "  }, 1000);\n",
+    "})();</script>\n",
+);
+
+/// Binds a server to `port` on localhost.
+pub fn bind(port: u16) -> Result<Server> {
+    Server::http(("127.0.0.1", port)).map_err(|error| Error::Server(error.to_string()))
+}
+
+/// Serves the files of `root` with `server`.
+///
+/// A script is injected into HTML pages, which reloads the page whenever
+/// `version` changes.
+pub fn serve(server: &Server, root: &Path, version: &AtomicU64) -> Result<()> {
+    for request in server.incoming_requests() {
+        if let Err(error) = respond(root, version, request) {
+            write::error(Error::from(error))?;
+        }
+    }
+    Ok(())
+}
+
+fn respond(root: &Path, version: &AtomicU64, request: Request) -> io::Result<()> {
+    let url = request.url().split(['?', '#']).next().unwrap_or_default();
+    if url == LIVE_RELOAD_PATH {
+        let version = version.load(Ordering::Relaxed).to_string();
+        return request.respond(Response::from_string(version));
+    }
+    let Some(path) = resolve(root, url) else {
+        return request.respond(Response::from_string("not found").with_status_code(404));
+    };
+    let mut data = fs::read(&path)?;
+    let content_type = content_type(&path);
+    if content_type.starts_with("text/html") {
+        data = inject_live_reload(&String::from_utf8_lossy(&data)).into_bytes();
+    }
+    let header = Header::from_bytes("Content-Type", content_type).unwrap();
+    request.respond(Response::from_data(data).with_header(header))
+}
+
+fn content_type(path: &Path) -> &'static str {
+    match path.extension().and_then(OsStr::to_str) {
+        Some("css") => "text/css; charset=utf-8",
+        Some("gif") => "image/gif",
+        Some("html" | "htm") => "text/html; charset=utf-8",
+        Some("jpeg" | "jpg") => "image/jpeg",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("md" | "txt") => "text/plain; charset=utf-8",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("woff2") => "font/woff2",
+        Some("xml") => "application/xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn inject_live_reload(html: &str) -> String {
+    let index = html.rfind("</body>").unwrap_or(html.len());
+    let mut html = html.to_string();
+    html.insert_str(index, LIVE_RELOAD_SCRIPT);
+    html
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Returns `None` if the URL path does not point to a file within `root`.
+fn resolve(root: &Path, url: &str) -> Option<PathBuf> {
+    let mut path = root.to_path_buf();
+    for segment in percent_decode(url)?.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => return None,
+            segment => path.push(segment),
+        }
+    }
+    if path.is_dir() {
+        path.push("index.html");
+    }
+    path.is_file().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inject_live_reload() {
+        let html = inject_live_reload("<body></body>");
+        assert!(html.starts_with("<body><script>"));
+        assert!(html.ends_with("</script>\n</body>"));
+        assert!(inject_live_reload("text").starts_with("text<script>"));
+    }
+
+    #[test]
+    fn test_resolve() -> io::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let root = temp_dir.path().join("root");
+        fs::create_dir_all(root.join("a dir"))?;
+        fs::write(root.join("index.html"), "")?;
+        fs::write(root.join("a dir/file.html"), "")?;
+        fs::write(temp_dir.path().join("secret"), "")?;
+        assert_eq!(resolve(&root, "/"), Some(root.join("index.html")));
+        assert_eq!(
+            resolve(&root, "/a%20dir/file.html"),
+            Some(root.join("a dir/file.html"))
+        );
+        assert_eq!(resolve(&root, "/a%20dir"), None);
+        assert_eq!(resolve(&root, "/../secret"), None);
+        Ok(())
+    }
+}
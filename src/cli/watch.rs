@@ -0,0 +1,88 @@
+use std::{fs, path::Path, sync::mpsc, time::Duration};
+
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+
+use crate::{
+    error::{Error, Result},
+    terminal::message::write,
+};
+
+// wait for this long after the last change before calling back (e.g. editors write multiple files)
+const DEBOUNCE_DURATION: Duration = Duration::from_millis(200);
+
+/// Calls `on_change` whenever something within `paths` changes.
+///
+/// Changes within `ignored_path` are ignored. All paths are canonicalized,
+/// so they must exist. Errors of the watcher and errors returned by
+/// `on_change` are written to stderr, so this only returns if watching can
+/// not be started.
+pub fn watch<F>(paths: &[(&Path, RecursiveMode)], ignored_path: &Path, mut on_change: F) -> Result<()>
+where
+    F: FnMut() -> Result<()>,
+{
+    let (sender, receiver) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    for (path, mode) in paths {
+        watcher.watch(&fs::canonicalize(path)?, *mode)?;
+    }
+    let ignored_path = fs::canonicalize(ignored_path)?;
+    while let Ok(event) = receiver.recv() {
+        let event = match event {
+            Ok(event) => event,
+            Err(error) => {
+                write::error(Error::from(error))?;
+                continue;
+            }
+        };
+        if is_ignored(&event, &ignored_path) {
+            continue;
+        }
+        while receiver.recv_timeout(DEBOUNCE_DURATION).is_ok() {}
+        if let Err(error) = on_change() {
+            write::error(error)?;
+        }
+    }
+    Ok(())
+}
+
+fn is_ignored(event: &Event, ignored_path: &Path) -> bool {
+    // reading files (e.g. while building) triggers access events
+    matches!(event.kind, EventKind::Access(_))
+        || event
+            .paths
+            .iter()
+            .all(|path| path.starts_with(ignored_path))
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{AccessKind, ModifyKind};
+
+    use super::*;
+
+    #[test]
+    fn test_is_ignored() {
+        let ignored_path = Path::new("/recipes/output");
+        let modify = |paths: &[&str]| {
+            paths.iter().fold(
+                Event::new(EventKind::Modify(ModifyKind::Any)),
+                |event, path| event.add_path(path.into()),
+            )
+        };
+        assert!(is_ignored(
+            &modify(&["/recipes/output/index.html"]),
+            ignored_path
+        ));
+        assert!(!is_ignored(&modify(&["/recipes/a/a.recipe"]), ignored_path));
+        assert!(!is_ignored(
+            &modify(&["/recipes/output/index.html", "/recipes/a/a.recipe"]),
+            ignored_path
+        ));
+        assert!(!is_ignored(
+            &modify(&["/recipes/output-old/a.html"]),
+            ignored_path
+        ));
+        let access = Event::new(EventKind::Access(AccessKind::Any)).add_path("/recipes/a".into());
+        assert!(is_ignored(&access, ignored_path));
+    }
+}
@@ -1,4 +1,4 @@
-use std::{io, path::PathBuf};
+use std::{fmt::Write, io, path::PathBuf};
 
 use crate::recipe::errors::ParseError;
 
@@ -6,12 +6,36 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
+    #[error("cyclic recipe references: '{0}'")]
+    CyclicRecipeReference(String),
+    #[error("recipe '{0}' references unknown recipe '{1}'")]
+    DanglingRecipeReference(String, String),
     #[error("a recipe title must contain non-whitespace characters")]
     EmptyRecipeTitle,
+    #[error("invalid font: {0}")]
+    InvalidFont(&'static str),
+    #[error("invalid captions file format: {0}")]
+    InvalidCaptionsFileFormat(#[source] toml::de::Error),
+    #[error("invalid image '{0}': {1}")]
+    InvalidImage(PathBuf, #[source] image::ImageError),
     #[error("invalid image file extension: '{0}'")]
     InvalidImageFileExt(PathBuf),
+    #[error("invalid pantry file format: {0}")]
+    InvalidPantryFileFormat(#[source] toml::de::Error),
+    #[error("invalid pantry item '{0}': {1}")]
+    InvalidPantryItem(String, #[source] ParseError),
+    #[error("invalid path pattern '{0}': {1}")]
+    InvalidPathPattern(String, &'static str),
+    #[error("invalid price of '{0}': {1}")]
+    InvalidPrice(String, #[source] ParseError),
+    #[error("invalid prices file format: {0}")]
+    InvalidPricesFileFormat(#[source] toml::de::Error),
     #[error("invalid language file format: {0}")]
     InvalidLanguageFileFormat(#[from] toml::de::Error),
+    #[error("invalid redirects file format: {0}")]
+    InvalidRedirectsFileFormat(#[source] toml::de::Error),
+    #[error("invalid synonyms file format: {0}")]
+    InvalidSynonymsFileFormat(#[source] toml::de::Error),
     #[error(transparent)]
     Io(#[from] io::Error),
     #[error(transparent)]
@@ -20,6 +44,41 @@ pub enum Error {
     MissingImageFileExt(PathBuf),
     #[error("missing template file: '{0}'")]
     MissingTemplateFile(String),
+    #[error("{} errors occurred:{}", .0.len(), format_list(.0))]
+    Multiple(Vec<Error>),
+    #[error("recipes '{1}' and '{2}' have the same output name: '{0}'")]
+    OutputNameCollision(String, String, String),
+    #[error("tags '{1}' and '{2}' have the same output name: '{0}'")]
+    TagNameCollision(String, String, String),
     #[error(transparent)]
     Tera(#[from] tera::Error),
+    #[error("zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+/// Collects all values of `results` or all errors, if there are any.
+pub(crate) fn collect<T, I>(results: I) -> Result<Vec<T>>
+where
+    I: IntoIterator<Item = Result<T>>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+    match errors.len() {
+        0 => Ok(values),
+        1 => Err(errors.pop().unwrap()),
+        _ => Err(Error::Multiple(errors)),
+    }
+}
+
+fn format_list(errors: &[Error]) -> String {
+    errors.iter().fold(String::new(), |mut text, error| {
+        write!(text, "\n  - {error}").unwrap();
+        text
+    })
 }
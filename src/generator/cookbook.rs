@@ -0,0 +1,313 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsString,
+    path::Path,
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use tera::Context;
+
+use crate::{
+    error::{self, Result},
+    recipe::{cost::CostedRecipe, synonyms::Synonyms},
+    template,
+};
+
+use super::{
+    Generator, Source, TextFilter, create_file,
+    image::Image,
+    image_values,
+    layout::base_url,
+    manifest::{Entry, Hasher, Manifest},
+};
+
+#[derive(Debug, Serialize)]
+struct CookbookEntry {
+    title: String,
+    /// Unique within the cookbook, e.g. to link to the recipe
+    anchor: String,
+    tags: Vec<String>,
+    image: Option<Value>,
+    gallery: Vec<Value>,
+    /// Links to the referenced recipes included in the cookbook by title
+    references: BTreeMap<String, String>,
+    recipe: CostedRecipe,
+}
+
+/// Recipes sharing the same chapter tag, `tag` is `None` for untagged recipes.
+#[derive(Debug, Serialize)]
+pub(super) struct Chapter<'a, T> {
+    pub tag: Option<&'a str>,
+    pub recipes: Vec<&'a T>,
+}
+
+#[derive(Debug, Serialize)]
+struct RecipeRef<'a> {
+    title: &'a str,
+    anchor: &'a str,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexedIngredient<'a> {
+    name: &'a str,
+    recipes: Vec<RecipeRef<'a>>,
+}
+
+impl<F: TextFilter + Sync> Generator<F> {
+    /// Renders all recipes of `recipe_dir` into a single cookbook document
+    /// (at the index path of the layout) with its images into `output_dir`.
+    ///
+    /// If `tags` is not empty, only recipes with at least one of these tags
+    /// are included and the chapters follow the order of `tags`. Otherwise
+    /// there is a chapter per first recipe tag in alphabetical order.
+    pub fn generate_cookbook(
+        &self,
+        recipe_dir: &Path,
+        output_dir: &Path,
+        tags: &[String],
+    ) -> Result<()> {
+        self.engine.require_template(template::COOKBOOK_NAME)?;
+        self.layout.validate()?;
+        let previous = Manifest::load(output_dir)?.unwrap_or_default();
+        let mut hasher = Hasher::default();
+        self.engine.hash_into(&mut hasher)?;
+        hasher.update(format!("{:?}", self.layout));
+        hasher.update(format!("{:?}", self.file_name_filter));
+        hasher.update(format!("{:?}", self.synonyms));
+        let mut manifest = Manifest::new(hasher.finish());
+        let path = self.layout.index_path(&self.output_file_ext);
+        let base_url = base_url(&path);
+        let sources: Vec<_> = self
+            .load_sources(recipe_dir)?
+            .into_iter()
+            .filter(|source| {
+                tags.is_empty()
+                    || source
+                        .recipe
+                        .metadata
+                        .tags
+                        .iter()
+                        .any(|tag| tags.contains(tag))
+            })
+            .collect();
+        let anchors: HashMap<_, _> = sources
+            .iter()
+            .map(|source| (source.directory.base_name().to_owned(), source.name.clone()))
+            .collect();
+        let results: Vec<_> = sources
+            .into_par_iter()
+            .map(|source| {
+                let key = source.directory.base_name().to_string_lossy().into_owned();
+                let previous_entry = previous
+                    .recipes
+                    .get(&key)
+                    .filter(|entry| !self.clean && entry.hash == source.hash);
+                let (image, gallery) =
+                    self.process_images(&source, &path, previous_entry, output_dir)?;
+                let entry = Entry {
+                    hash: source.hash.clone(),
+                    files: image
+                        .iter()
+                        .chain(&gallery)
+                        .flat_map(Image::files)
+                        .collect(),
+                    image,
+                    gallery,
+                };
+                let cookbook_entry = self.cookbook_entry(source, &entry, &base_url, &anchors);
+                Ok((key, entry, cookbook_entry))
+            })
+            .collect();
+        let mut entries = Vec::new();
+        for (key, entry, cookbook_entry) in error::collect(results)? {
+            manifest.recipes.insert(key, entry);
+            entries.push(cookbook_entry);
+        }
+        entries.sort_by(|a, b| a.title.cmp(&b.title));
+        let mut context = Context::new();
+        context.insert("recipes", &entries);
+        context.insert(
+            "chapters",
+            &chapters(&entries, tags, |entry| &entry.recipe.metadata.tags),
+        );
+        context.insert(
+            "ingredient_index",
+            &ingredient_index(&entries, &self.synonyms),
+        );
+        context.insert("path", &path);
+        context.insert("base_url", &base_url);
+        let file = create_file(&output_dir.join(&path))?;
+        self.engine.render_cookbook(context, file)?;
+        manifest.pages.push(path);
+        self.copy_static_files(output_dir, &previous, &mut manifest)?;
+        manifest.remove_stale_files(&previous, output_dir)?;
+        manifest.store(output_dir)?;
+        Ok(())
+    }
+
+    fn cookbook_entry(
+        &self,
+        source: Source,
+        entry: &Entry,
+        base_url: &str,
+        anchors: &HashMap<OsString, String>,
+    ) -> CookbookEntry {
+        let (image, gallery) = image_values(&source, entry, base_url);
+        let references = source
+            .references
+            .iter()
+            .filter_map(|(title, name)| {
+                let anchor = anchors.get(name)?;
+                Some((title.clone(), format!("#recipe-{anchor}")))
+            })
+            .collect();
+        let mut tags = source.recipe.metadata.tags.clone();
+        tags.sort();
+        CookbookEntry {
+            title: source.recipe.title.clone(),
+            anchor: source.name,
+            tags,
+            image,
+            gallery,
+            references,
+            recipe: source.recipe,
+        }
+    }
+}
+
+/// Groups the entries by the first tag of their recipe (of `tags`, if not empty).
+pub(super) fn chapters<'a, T>(
+    entries: &'a [T],
+    tags: &'a [String],
+    recipe_tags: impl Fn(&T) -> &[String],
+) -> Vec<Chapter<'a, T>> {
+    let mut chapters: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for entry in entries {
+        let recipe_tags = recipe_tags(entry);
+        let tag = if tags.is_empty() {
+            recipe_tags.first()
+        } else {
+            recipe_tags.iter().find(|tag| tags.contains(tag))
+        };
+        // untagged recipes (`None`) come last
+        let position = match tag {
+            Some(tag) => tags.iter().position(|other| other == tag).unwrap_or(0),
+            None => usize::MAX,
+        };
+        chapters
+            .entry((position, tag.map(String::as_str)))
+            .or_default()
+            .push(entry);
+    }
+    chapters
+        .into_iter()
+        .map(|((_, tag), recipes)| Chapter { tag, recipes })
+        .collect()
+}
+
+/// Lists all ingredients (case-insensitive and grouped by their synonyms) in
+/// alphabetical order with the recipes using them.
+fn ingredient_index<'a>(
+    entries: &'a [CookbookEntry],
+    synonyms: &'a Synonyms,
+) -> Vec<IndexedIngredient<'a>> {
+    let mut ingredients: BTreeMap<_, IndexedIngredient> = BTreeMap::new();
+    for entry in entries {
+        for ingredient in entry.recipe.ingredients.iter() {
+            let indexed = ingredients
+                .entry(synonyms.id(&ingredient.name))
+                .or_insert_with(|| IndexedIngredient {
+                    name: synonyms
+                        .canonical_name(&ingredient.name)
+                        .unwrap_or(&ingredient.name),
+                    recipes: Vec::new(),
+                });
+            if indexed
+                .recipes
+                .last()
+                .is_none_or(|recipe| recipe.anchor != entry.anchor)
+            {
+                indexed.recipes.push(RecipeRef {
+                    title: &entry.title,
+                    anchor: &entry.anchor,
+                });
+            }
+        }
+    }
+    ingredients.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, fs, io::Cursor};
+
+    use tempfile::tempdir;
+
+    use crate::recipe::{Recipe, directory::Directory};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FileNameFilter;
+
+    impl TextFilter for FileNameFilter {
+        fn filter<S: AsRef<str>>(&self, text: S) -> String {
+            text.as_ref().to_lowercase()
+        }
+    }
+
+    #[test]
+    fn test_generate_cookbook() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join("recipes");
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(
+            tera_dir.join("cookbook.html"),
+            concat!(
+                "{% for c in chapters %}{{ c.tag | default(value=\"-\") }}:",
+                "{% for r in c.recipes %}{{ r.anchor }}{% endfor %} {% endfor %}|",
+                "{% for i in ingredient_index %}{{ i.name }}=",
+                "{% for r in i.recipes %}{{ r.title }}{% endfor %} {% endfor %}",
+            ),
+        )?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- Salt\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        for (title, tags) in [("C", vec!["x"]), ("B", vec![]), ("A", vec!["y", "x"])] {
+            recipe.title = title.into();
+            recipe.metadata.tags = tags.into_iter().map(Into::into).collect();
+            Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        }
+        recipe.title = "D".into();
+        recipe.ingredients = Recipe::parse_from(Cursor::new(
+            "title\n\nYield: 1\n\nIngredients\n- salt\n- pepper\n\nInstructions\n- none",
+        ))?
+        .ingredients;
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+
+        let engine = template::Engine::new(&tpl_dir, false, "html", None)?;
+        let generator = Generator::new(engine, Vec::new(), OsString::from("html"), FileNameFilter);
+        let read = || fs::read_to_string(output_dir.join("index.html"));
+
+        // chapters by first tag, untagged recipes last
+        generator.generate_cookbook(&recipe_dir, &output_dir, &[])?;
+        assert_eq!(read()?, "x:c y:ad -:b |pepper=D Salt=ABCD ");
+
+        // only recipes with one of the tags, chapters in the order of the tags
+        generator.generate_cookbook(&recipe_dir, &output_dir, &["y".into(), "x".into()])?;
+        assert_eq!(read()?, "y:ad x:c |pepper=D Salt=ACD ");
+
+        // a site cannot be generated without recipe template
+        assert!(generator.generate(&recipe_dir, &output_dir).is_err());
+        Ok(())
+    }
+}
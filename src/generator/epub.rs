@@ -0,0 +1,439 @@
+//! EPUB 3 export with a chapter per recipe.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fmt::Write as _,
+    fs,
+    io::{Seek, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tera::Context;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    APP_NAME,
+    error::{self, Result},
+    recipe::cost::CostedRecipe,
+    template,
+    util::{escape_xml, list_files, timestamp},
+};
+
+use super::{
+    Generator, STATIC_DIR, TextFilter, cookbook, create_file,
+    image::Image,
+    image_values,
+    layout::base_url,
+    manifest::{Entry, Hasher, Manifest},
+};
+
+pub const EPUB_EXTENSION: &str = "epub";
+
+const MIMETYPE: &str = "application/epub+zip";
+const PACKAGE_DIR: &str = "EPUB";
+const PACKAGE_FILE_NAME: &str = "package.opf";
+const NAV_PATH: &str = "nav.xhtml";
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+/// Metadata of EPUB files.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: String,
+    pub author: Option<String>,
+}
+
+impl Default for Metadata {
+    fn default() -> Self {
+        Self {
+            title: "Cookbook".into(),
+            author: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct EpubEntry {
+    title: String,
+    /// Path of the chapter within the package
+    path: PathBuf,
+    tags: Vec<String>,
+    image: Option<Value>,
+    gallery: Vec<Value>,
+    recipe: CostedRecipe,
+    #[serde(skip)]
+    content: Vec<u8>,
+}
+
+impl<F: TextFilter + Sync> Generator<F> {
+    /// Renders all recipes of `recipe_dir` into an EPUB file (at the index
+    /// path of the layout, with the extension `epub`) in `output_dir`.
+    ///
+    /// Each recipe is a chapter rendered by the recipe template, the index
+    /// template renders the navigation document with `chapters` like
+    /// [`Generator::generate_cookbook`]. Processed images are kept in
+    /// `output_dir` for subsequent builds.
+    pub fn generate_epub(
+        &self,
+        recipe_dir: &Path,
+        output_dir: &Path,
+        tags: &[String],
+        metadata: &Metadata,
+    ) -> Result<()> {
+        self.engine.require_template(template::RECIPE_NAME)?;
+        self.engine.require_template(template::INDEX_NAME)?;
+        self.layout.validate()?;
+        let previous = Manifest::load(output_dir)?.unwrap_or_default();
+        let mut hasher = Hasher::default();
+        self.engine.hash_into(&mut hasher)?;
+        hasher.update(format!("{:?}", self.layout));
+        hasher.update(format!("{:?}", self.file_name_filter));
+        let mut manifest = Manifest::new(hasher.finish());
+        let sources: Vec<_> = self
+            .load_sources(recipe_dir)?
+            .into_iter()
+            .filter(|source| {
+                tags.is_empty()
+                    || source
+                        .recipe
+                        .metadata
+                        .tags
+                        .iter()
+                        .any(|tag| tags.contains(tag))
+            })
+            .collect();
+        let chapter_paths: HashMap<_, _> = sources
+            .iter()
+            .map(|source| {
+                let path = self.recipe_path(&source.name, &source.recipe);
+                (source.directory.base_name().to_owned(), path)
+            })
+            .collect();
+        let results: Vec<_> = sources
+            .into_par_iter()
+            .map(|source| {
+                let path = self.recipe_path(&source.name, &source.recipe);
+                let key = source.directory.base_name().to_string_lossy().into_owned();
+                let previous_entry = previous
+                    .recipes
+                    .get(&key)
+                    .filter(|entry| !self.clean && entry.hash == source.hash);
+                let (image, gallery) =
+                    self.process_images(&source, &path, previous_entry, output_dir)?;
+                let entry = Entry {
+                    hash: source.hash.clone(),
+                    files: image
+                        .iter()
+                        .chain(&gallery)
+                        .flat_map(Image::files)
+                        .collect(),
+                    image,
+                    gallery,
+                };
+                let base_url = base_url(&path);
+                let (image, gallery) = image_values(&source, &entry, &base_url);
+                let mut context = Context::new();
+                context.insert("recipe", &source.recipe);
+                context.insert("path", &path);
+                context.insert("image", &image);
+                context.insert("gallery", &gallery);
+                context.insert("base_url", &base_url);
+                // only recipes included as chapters can be linked
+                let references: BTreeMap<_, _> = source
+                    .references
+                    .iter()
+                    .filter_map(|(title, name)| {
+                        let path = chapter_paths.get(name)?;
+                        Some((title, format!("{base_url}/{}", href(path))))
+                    })
+                    .collect();
+                context.insert("references", &references);
+                let mut content = Vec::new();
+                self.engine.render_recipe(context, &mut content)?;
+                let recipe = source.recipe;
+                let mut tags = recipe.metadata.tags.clone();
+                tags.sort();
+                let epub_entry = EpubEntry {
+                    title: recipe.title.clone(),
+                    path,
+                    tags,
+                    image,
+                    gallery,
+                    recipe,
+                    content,
+                };
+                Ok((key, entry, epub_entry))
+            })
+            .collect();
+        let mut entries = Vec::new();
+        for (key, entry, epub_entry) in error::collect(results)? {
+            manifest.recipes.insert(key, entry);
+            entries.push(epub_entry);
+        }
+        entries.sort_by(|a, b| a.title.cmp(&b.title));
+        let chapters = cookbook::chapters(&entries, tags, |entry| &entry.recipe.metadata.tags);
+        let mut context = Context::new();
+        context.insert("recipes", &entries);
+        context.insert("chapters", &chapters);
+        context.insert("title", &metadata.title);
+        context.insert("author", &metadata.author);
+        context.insert("base_url", &base_url(Path::new(NAV_PATH)));
+        let mut nav = Vec::new();
+        self.engine.render_index(context, &mut nav)?;
+
+        // the reading order follows the navigation document
+        let spine: Vec<_> = chapters
+            .iter()
+            .flat_map(|chapter| &chapter.recipes)
+            .copied()
+            .collect();
+        let mut files: Vec<_> = manifest
+            .recipes
+            .values()
+            .flat_map(|entry| &entry.files)
+            .map(|path| (path.clone(), output_dir.join(path)))
+            .collect();
+        let static_path = self.engine.static_path();
+        if static_path.exists() {
+            files.extend(list_files(static_path)?.into_iter().map(|path| {
+                let source = static_path.join(&path);
+                (Path::new(STATIC_DIR).join(path), source)
+            }));
+        }
+        let package = Package {
+            metadata,
+            language: self.engine.language.as_deref().unwrap_or("en"),
+            nav: &nav,
+            chapters: &spine,
+            files: &files,
+        };
+        let path = self.layout.index_path(OsStr::new(EPUB_EXTENSION));
+        package.write(create_file(&output_dir.join(&path))?)?;
+        manifest.pages.push(path);
+        manifest.remove_stale_files(&previous, output_dir)?;
+        manifest.store(output_dir)?;
+        Ok(())
+    }
+}
+
+/// Contents of an EPUB file, all paths are relative to the package directory.
+struct Package<'a> {
+    metadata: &'a Metadata,
+    language: &'a str,
+    nav: &'a [u8],
+    chapters: &'a [&'a EpubEntry],
+    /// Images and static files with their source paths
+    files: &'a [(PathBuf, PathBuf)],
+}
+
+impl Package<'_> {
+    fn write(&self, writer: impl Write + Seek) -> Result<()> {
+        let mut zip = ZipWriter::new(writer);
+        // the mimetype must be the first and an uncompressed file
+        let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        zip.start_file("mimetype", stored)?;
+        zip.write_all(MIMETYPE.as_bytes())?;
+        let options = SimpleFileOptions::default();
+        zip.start_file("META-INF/container.xml", options)?;
+        zip.write_all(CONTAINER.as_bytes())?;
+        zip.start_file(package_path(Path::new(PACKAGE_FILE_NAME)), options)?;
+        zip.write_all(self.package_document().as_bytes())?;
+        zip.start_file(package_path(Path::new(NAV_PATH)), options)?;
+        zip.write_all(self.nav)?;
+        for chapter in self.chapters {
+            zip.start_file(package_path(&chapter.path), options)?;
+            zip.write_all(&chapter.content)?;
+        }
+        for (path, source) in self.files {
+            zip.start_file(package_path(path), options)?;
+            zip.write_all(&fs::read(source)?)?;
+        }
+        zip.finish()?;
+        Ok(())
+    }
+
+    /// Returns the package document (OPF) with metadata, manifest and spine.
+    fn package_document(&self) -> String {
+        let mut hasher = Hasher::default();
+        hasher.update(&self.metadata.title);
+        hasher.update(self.metadata.author.as_deref().unwrap_or_default());
+        let mut opf = String::new();
+        opf.push_str(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"id\">\n",
+            "  <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n",
+        ));
+        let identifier = format!("urn:{APP_NAME}:{}", hasher.finish());
+        let _ = writeln!(
+            opf,
+            "    <dc:identifier id=\"id\">{identifier}</dc:identifier>"
+        );
+        let _ = writeln!(
+            opf,
+            "    <dc:title>{}</dc:title>",
+            escape_xml(&self.metadata.title)
+        );
+        if let Some(author) = &self.metadata.author {
+            let _ = writeln!(opf, "    <dc:creator>{}</dc:creator>", escape_xml(author));
+        }
+        let _ = writeln!(
+            opf,
+            "    <dc:language>{}</dc:language>",
+            escape_xml(self.language)
+        );
+        let _ = writeln!(
+            opf,
+            "    <meta property=\"dcterms:modified\">{}</meta>",
+            timestamp(SystemTime::now())
+        );
+        opf.push_str("  </metadata>\n  <manifest>\n");
+        let _ = writeln!(
+            opf,
+            "    <item id=\"nav\" href=\"{NAV_PATH}\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>"
+        );
+        for (index, chapter) in self.chapters.iter().enumerate() {
+            let _ = writeln!(
+                opf,
+                "    <item id=\"recipe-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>",
+                index + 1,
+                escape_xml(&href(&chapter.path))
+            );
+        }
+        for (index, (path, _)) in self.files.iter().enumerate() {
+            let _ = writeln!(
+                opf,
+                "    <item id=\"file-{}\" href=\"{}\" media-type=\"{}\"/>",
+                index + 1,
+                escape_xml(&href(path)),
+                media_type(path)
+            );
+        }
+        opf.push_str("  </manifest>\n  <spine>\n");
+        for index in 0..self.chapters.len() {
+            let _ = writeln!(opf, "    <itemref idref=\"recipe-{}\"/>", index + 1);
+        }
+        opf.push_str("  </spine>\n</package>\n");
+        opf
+    }
+}
+
+/// Returns the path of a file within the EPUB file.
+fn package_path(path: &Path) -> String {
+    format!("{PACKAGE_DIR}/{}", href(path))
+}
+
+fn href(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn media_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase());
+    match ext.as_deref() {
+        Some("css") => "text/css",
+        Some("gif") => "image/gif",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        Some("webp") => "image/webp",
+        Some("xhtml") => "application/xhtml+xml",
+        Some("otf") => "font/otf",
+        Some("ttf") => "font/ttf",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{ffi::OsString, io::Cursor};
+
+    use tempfile::tempdir;
+    use zip::ZipArchive;
+
+    use crate::recipe::{Recipe, directory::Directory};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct FileNameFilter;
+
+    impl TextFilter for FileNameFilter {
+        fn filter<S: AsRef<str>>(&self, text: S) -> String {
+            text.as_ref().to_lowercase()
+        }
+    }
+
+    #[test]
+    fn test_generate_epub() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join("recipes");
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(tpl_dir.join("static"))?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(tera_dir.join("recipe.xhtml"), "{{ recipe.title }}")?;
+        fs::write(
+            tera_dir.join("index.xhtml"),
+            "{% for c in chapters %}{{ c.tag }}:{% for r in c.recipes %}{{ r.path }}{% endfor %} {% endfor %}",
+        )?;
+        fs::write(tpl_dir.join("static/style.css"), "")?;
+
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- Salt\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        for (title, tag) in [("B", "x"), ("A", "y"), ("C", "z")] {
+            recipe.title = title.into();
+            recipe.metadata.tags = vec![tag.into()];
+            Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        }
+
+        let engine = template::Engine::new(&tpl_dir, false, "xhtml", None)?;
+        let mut generator =
+            Generator::new(engine, Vec::new(), OsString::from("xhtml"), FileNameFilter);
+        generator.layout.index_path = "book.{ext}".into();
+        let metadata = Metadata {
+            title: "Recipes & more".into(),
+            author: Some("Me".into()),
+        };
+        let tags = ["y".to_string(), "x".to_string()];
+        generator.generate_epub(&recipe_dir, &output_dir, &tags, &metadata)?;
+
+        let mut epub = ZipArchive::new(fs::File::open(output_dir.join("book.epub"))?)?;
+        let read = |epub: &mut ZipArchive<_>, name: &str| {
+            let mut file = epub.by_name(name).unwrap();
+            std::io::read_to_string(&mut file).unwrap()
+        };
+        assert_eq!(epub.by_index(0)?.name(), "mimetype");
+        assert_eq!(read(&mut epub, "mimetype"), MIMETYPE);
+        assert_eq!(
+            read(&mut epub, "EPUB/nav.xhtml"),
+            "y:recipes/a.xhtml x:recipes/b.xhtml "
+        );
+        assert_eq!(read(&mut epub, "EPUB/recipes/b.xhtml"), "B");
+        assert!(epub.by_name("EPUB/recipes/c.xhtml").is_err());
+        let opf = read(&mut epub, "EPUB/package.opf");
+        assert!(opf.contains("<dc:title>Recipes &amp; more</dc:title>"));
+        assert!(opf.contains("<dc:creator>Me</dc:creator>"));
+        assert!(opf.contains("href=\"static/style.css\" media-type=\"text/css\""));
+        let spine = opf.find("idref=\"recipe-1\"").unwrap();
+        assert!(spine < opf.find("idref=\"recipe-2\"").unwrap());
+        assert!(read(&mut epub, "EPUB/static/style.css").is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+//! Atom feed of the most recently added or changed recipes.
+
+use std::{fmt::Write as _, path::Path, time::UNIX_EPOCH};
+
+use serde::Deserialize;
+
+use crate::{
+    APP_NAME,
+    util::{escape_xml, timestamp},
+};
+
+use super::{IndexEntry, layout::absolute_url};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct FeedOptions {
+    /// Output path, relative to the output directory
+    pub path: String,
+    pub title: String,
+    /// Author of the feed, defaults to the title
+    pub author: Option<String>,
+    /// Maximum number of recipes
+    pub size: usize,
+}
+
+impl Default for FeedOptions {
+    fn default() -> Self {
+        Self {
+            path: "feed.xml".into(),
+            title: "Recipes".into(),
+            author: None,
+            size: 20,
+        }
+    }
+}
+
+/// Renders the feed of `entries`, newest first, with URLs relative to `site_url`.
+pub(super) fn render(entries: &[IndexEntry], options: &FeedOptions, site_url: &str) -> String {
+    let mut entries: Vec<_> = entries.iter().collect();
+    entries.sort_by(|a, b| b.dates.updated.cmp(&a.dates.updated));
+    entries.truncate(options.size);
+    let updated = entries.first().map_or_else(
+        || timestamp(UNIX_EPOCH),
+        |entry| entry.dates.updated.clone(),
+    );
+    let home_url = absolute_url(site_url, Path::new(""));
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    let _ = writeln!(xml, "  <title>{}</title>", escape_xml(&options.title));
+    let _ = writeln!(xml, "  <id>{}</id>", escape_xml(&home_url));
+    let _ = writeln!(xml, "  <link href=\"{}\"/>", escape_xml(&home_url));
+    let _ = writeln!(
+        xml,
+        "  <link rel=\"self\" href=\"{}\"/>",
+        escape_xml(&absolute_url(site_url, Path::new(&options.path)))
+    );
+    let _ = writeln!(xml, "  <updated>{updated}</updated>");
+    let author = options.author.as_ref().unwrap_or(&options.title);
+    let _ = writeln!(
+        xml,
+        "  <author><name>{}</name></author>",
+        escape_xml(author)
+    );
+    let _ = writeln!(xml, "  <generator>{APP_NAME}</generator>");
+    for entry in entries {
+        let url = escape_xml(&absolute_url(site_url, &entry.path));
+        xml.push_str("  <entry>\n");
+        let _ = writeln!(xml, "    <title>{}</title>", escape_xml(&entry.title));
+        let _ = writeln!(xml, "    <id>{url}</id>");
+        let _ = writeln!(xml, "    <link href=\"{url}\"/>");
+        let _ = writeln!(xml, "    <published>{}</published>", entry.dates.added);
+        let _ = writeln!(xml, "    <updated>{}</updated>", entry.dates.updated);
+        if let Some(summary) = &entry.summary {
+            let _ = writeln!(xml, "    <summary>{}</summary>", escape_xml(summary));
+        }
+        for tag in &entry.tags {
+            let _ = writeln!(xml, "    <category term=\"{}\"/>", escape_xml(tag));
+        }
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, path::PathBuf};
+
+    use super::*;
+    use crate::{
+        error::Result,
+        generator::Dates,
+        recipe::{Recipe, cost::CostedRecipe},
+    };
+
+    fn entry(title: &str, added: &str, updated: &str) -> Result<IndexEntry> {
+        let recipe = Recipe::parse_from(Cursor::new(format!(
+            "{title}\n\nYield: 1\nTags: a & b\n\nIngredients\n- nothing\n\nInstructions\n- none"
+        )))?;
+        Ok(IndexEntry {
+            title: recipe.title.clone(),
+            path: PathBuf::from(format!("recipes/{title}.html")),
+            tags: recipe.metadata.tags.clone(),
+            image_path: None,
+            image: None,
+            summary: recipe.summary(),
+            dates: Dates {
+                added: added.into(),
+                updated: updated.into(),
+            },
+            recipe: CostedRecipe { recipe, cost: None },
+        })
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let entries = [
+            entry("old", "2024-01-01T00:00:00Z", "2024-01-01T00:00:00Z")?,
+            entry("changed", "2024-01-02T00:00:00Z", "2024-03-01T08:00:00Z")?,
+            entry("new", "2024-02-01T00:00:00Z", "2024-02-01T00:00:00Z")?,
+        ];
+        let options = FeedOptions {
+            size: 2,
+            ..FeedOptions::default()
+        };
+        let xml = render(&entries, &options, "https://example.com/");
+        assert!(xml.contains("<updated>2024-03-01T08:00:00Z</updated>\n  <author>"));
+        assert!(xml.contains("<link rel=\"self\" href=\"https://example.com/feed.xml\"/>"));
+        assert!(xml.contains("<id>https://example.com/recipes/changed.html</id>"));
+        assert!(xml.contains("<published>2024-01-02T00:00:00Z</published>"));
+        assert!(xml.contains("<summary>none</summary>"));
+        assert!(xml.contains("<category term=\"a &amp; b\"/>"));
+        let changed = xml.find("<title>changed</title>").unwrap();
+        assert!(changed < xml.find("<title>new</title>").unwrap());
+        assert!(!xml.contains("<title>old</title>"));
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    fs,
+    io::BufWriter,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+};
+
+use image::{
+    DynamicImage, ImageDecoder, ImageFormat, ImageReader,
+    codecs::{jpeg::JpegEncoder, png::PngEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::create_file;
+
+const THUMBNAIL_SUFFIX: &str = "thumb";
+
+/// Output format of processed images.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Same format as the source image
+    #[default]
+    Original,
+    /// Lossless WebP
+    Webp,
+}
+
+/// Options of the image pipeline.
+///
+/// With the default options images are copied as they are, including their
+/// metadata (e.g. EXIF GPS positions). Processed images are re-encoded
+/// without metadata.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ImageOptions {
+    /// Widths of the responsive image variants (in pixels). The largest one
+    /// limits the width of the main image.
+    pub sizes: Vec<u32>,
+    /// Width of the thumbnail (in pixels), no thumbnail is created without it
+    pub thumbnail_width: Option<NonZeroU32>,
+    pub format: OutputFormat,
+    /// JPEG quality (1-100)
+    pub quality: u8,
+}
+
+impl Default for ImageOptions {
+    fn default() -> Self {
+        Self {
+            sizes: Vec::new(),
+            thumbnail_width: None,
+            format: OutputFormat::Original,
+            quality: 85,
+        }
+    }
+}
+
+impl ImageOptions {
+    fn thumbnail(&self) -> Option<u32> {
+        self.thumbnail_width.map(NonZeroU32::get)
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.sizes.is_empty()
+            || self.thumbnail().is_some()
+            || self.format != OutputFormat::Original
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Variant {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Output image of a recipe. All paths are relative to the output directory.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Image {
+    pub path: PathBuf,
+    /// Unknown for images that are copied unprocessed and cannot be decoded (e.g. SVG)
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Responsive variants in ascending width, including the main image
+    pub sources: Vec<Variant>,
+    pub thumbnail: Option<Variant>,
+}
+
+impl Image {
+    /// Returns all output files of the image.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files = vec![self.path.clone()];
+        let variants = self.sources.iter().chain(&self.thumbnail);
+        for variant in variants {
+            if !files.contains(&variant.path) {
+                files.push(variant.path.clone());
+            }
+        }
+        files
+    }
+
+    /// Returns the value of an HTML `srcset` attribute with URLs relative to `base_url`.
+    pub fn srcset(&self, base_url: &str) -> String {
+        self.sources
+            .iter()
+            .map(|variant| format!("{base_url}/{} {}w", variant.path.display(), variant.width))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Writes the image `source` and its variants to `path` (relative to `output_dir`).
+///
+/// The extension of `path` changes if the output format does. Images that
+/// are not decodable raster images (or animated, like GIF) are copied as
+/// they are.
+pub(crate) fn process(
+    source: &Path,
+    path: &Path,
+    output_dir: &Path,
+    options: &ImageOptions,
+) -> Result<Image> {
+    let format = ImageFormat::from_path(source).ok().filter(|format| {
+        matches!(
+            format,
+            ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP
+        )
+    });
+    let Some(format) = format.filter(|_| options.is_enabled()) else {
+        fs::create_dir_all(output_dir.join(path).parent().expect("invalid image path"))?;
+        fs::copy(source, output_dir.join(path))?;
+        let (width, height) = image::image_dimensions(source).ok().unzip();
+        return Ok(Image {
+            path: path.into(),
+            width,
+            height,
+            sources: Vec::new(),
+            thumbnail: None,
+        });
+    };
+    let (format, path) = match options.format {
+        OutputFormat::Original => (format, path.to_path_buf()),
+        OutputFormat::Webp => (ImageFormat::WebP, path.with_extension("webp")),
+    };
+    let invalid = |error| Error::InvalidImage(source.into(), error);
+    let mut decoder = ImageReader::open(source)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    // metadata is not written, so the orientation has to be applied to the pixels
+    image.apply_orientation(orientation);
+    if let Some(&max_width) = options.sizes.iter().max()
+        && image.width() > max_width
+    {
+        image = image.resize(max_width, u32::MAX, FilterType::Lanczos3);
+    }
+    let main = save(&image, &path, output_dir, format, options.quality)?;
+    let mut sources = Vec::new();
+    if !options.sizes.is_empty() {
+        let mut sizes = options.sizes.clone();
+        sizes.sort_unstable();
+        sizes.dedup();
+        for width in sizes.into_iter().filter(|&width| width < image.width()) {
+            let resized = image.resize(width, u32::MAX, FilterType::Lanczos3);
+            let path = variant_path(&path, &format!("{width}w"));
+            sources.push(save(&resized, &path, output_dir, format, options.quality)?);
+        }
+        sources.push(main.clone());
+    }
+    let thumbnail = match options.thumbnail() {
+        Some(width) => {
+            let thumbnail = if image.width() > width {
+                image.thumbnail(width, u32::MAX)
+            } else {
+                image.clone()
+            };
+            let path = variant_path(&path, THUMBNAIL_SUFFIX);
+            Some(save(
+                &thumbnail,
+                &path,
+                output_dir,
+                format,
+                options.quality,
+            )?)
+        }
+        None => None,
+    };
+    Ok(Image {
+        path,
+        width: Some(main.width),
+        height: Some(main.height),
+        sources,
+        thumbnail,
+    })
+}
+
+fn save(
+    image: &DynamicImage,
+    path: &Path,
+    output_dir: &Path,
+    format: ImageFormat,
+    quality: u8,
+) -> Result<Variant> {
+    let file = BufWriter::new(create_file(&output_dir.join(path))?);
+    let result = match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(file, quality)),
+        ImageFormat::Png => image.write_with_encoder(PngEncoder::new(file)),
+        _ => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(file)),
+    };
+    result.map_err(|error| Error::InvalidImage(path.into(), error))?;
+    Ok(Variant {
+        path: path.into(),
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+// e.g. `images/name.jpg` -> `images/name-480w.jpg`
+fn variant_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(format!("-{suffix}"));
+    if let Some(ext) = path.extension() {
+        file_name.push(".");
+        file_name.push(ext);
+    }
+    path.with_file_name(file_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_process() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let source = temp_dir.path().join("source.png");
+        RgbImage::new(40, 20).save(&source).unwrap();
+        let output_dir = temp_dir.path().join("output");
+        let path = Path::new("images/name.png");
+
+        // copy as is with default options
+        let image = process(&source, path, &output_dir, &ImageOptions::default())?;
+        assert_eq!(image.files(), [path]);
+        assert_eq!((image.width, image.height), (Some(40), Some(20)));
+        assert_eq!(fs::read(&source)?, fs::read(output_dir.join(path))?);
+
+        let options = ImageOptions {
+            sizes: vec![30, 10, 100],
+            thumbnail_width: NonZeroU32::new(8),
+            format: OutputFormat::Webp,
+            ..ImageOptions::default()
+        };
+        let image = process(&source, path, &output_dir, &options)?;
+        assert_eq!(image.path, Path::new("images/name.webp"));
+        assert_eq!((image.width, image.height), (Some(40), Some(20)));
+        assert_eq!(
+            image.srcset(".."),
+            "../images/name-10w.webp 10w, ../images/name-30w.webp 30w, ../images/name.webp 40w"
+        );
+        let thumbnail = image.thumbnail.as_ref().unwrap();
+        assert_eq!(thumbnail.path, Path::new("images/name-thumb.webp"));
+        assert_eq!((thumbnail.width, thumbnail.height), (8, 4));
+        for file in image.files() {
+            assert!(output_dir.join(file).exists());
+        }
+        let size = image::image_dimensions(output_dir.join("images/name-30w.webp")).unwrap();
+        assert_eq!(size, (30, 15));
+        Ok(())
+    }
+
+    #[test]
+    fn test_variant_path() {
+        assert_eq!(
+            variant_path(Path::new("images/name.jpg"), "480w"),
+            Path::new("images/name-480w.jpg")
+        );
+    }
+
+    #[test]
+    fn test_options() {
+        let options: ImageOptions = toml::from_str("thumbnail_width = 320").unwrap();
+        assert_eq!(options.thumbnail(), Some(320));
+        let options: ImageOptions = toml::from_str("sizes = []").unwrap();
+        assert!(!options.is_enabled());
+        assert!(toml::from_str::<ImageOptions>("thumbnail_width = 0").is_err());
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    ffi::OsStr,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+const SLUG_PLACEHOLDER: &str = "{slug}";
+const TAG_PLACEHOLDER: &str = "{tag}";
+const EXT_PLACEHOLDER: &str = "{ext}";
+
+/// Output paths of generated files, relative to the output directory.
+///
+/// Path patterns support the placeholders `{slug}` (output name of the
+/// recipe or tag), `{tag}` (first tag of the recipe, empty for untagged
+/// recipes) and `{ext}` (output file extension).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub recipe_path: String,
+    pub index_path: String,
+    pub tag_path: String,
+    pub ingredients_path: String,
+    pub image_dir: String,
+    pub images_next_to_recipes: bool,
+    /// Output path of the search index, none is generated if unset or empty
+    pub search_index_path: Option<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            recipe_path: "recipes/{slug}.{ext}".into(),
+            index_path: "index.{ext}".into(),
+            tag_path: "tags/{slug}.{ext}".into(),
+            ingredients_path: "ingredients.{ext}".into(),
+            image_dir: "images".into(),
+            images_next_to_recipes: false,
+            search_index_path: None,
+        }
+    }
+}
+
+impl Layout {
+    pub fn validate(&self) -> Result<()> {
+        for pattern in [&self.recipe_path, &self.tag_path] {
+            if !pattern.contains(SLUG_PLACEHOLDER) {
+                return Err(Error::InvalidPathPattern(
+                    pattern.clone(),
+                    "missing placeholder '{slug}'",
+                ));
+            }
+        }
+        let patterns = [
+            &self.recipe_path,
+            &self.index_path,
+            &self.tag_path,
+            &self.ingredients_path,
+            &self.image_dir,
+        ];
+        for pattern in patterns.into_iter().chain(self.search_index_pattern()) {
+            validate_relative_path(pattern)?;
+        }
+        Ok(())
+    }
+
+    /// Output path of a gallery image of the recipe `slug`.
+    pub(crate) fn gallery_path(&self, recipe_path: &Path, slug: &str, file_name: &str) -> PathBuf {
+        if self.images_next_to_recipes {
+            recipe_path.with_file_name(format!("{slug}-{file_name}"))
+        } else {
+            Path::new(&self.image_dir).join(slug).join(file_name)
+        }
+    }
+
+    pub(crate) fn index_path(&self, ext: &OsStr) -> PathBuf {
+        expand(
+            &self.index_path,
+            &[(EXT_PLACEHOLDER, &ext.to_string_lossy())],
+        )
+    }
+
+    pub(crate) fn ingredients_path(&self, ext: &OsStr) -> PathBuf {
+        expand(
+            &self.ingredients_path,
+            &[(EXT_PLACEHOLDER, &ext.to_string_lossy())],
+        )
+    }
+
+    pub(crate) fn image_path(&self, recipe_path: &Path, slug: &str, ext: &OsStr) -> PathBuf {
+        let file_name = format!("{slug}.{}", ext.to_string_lossy());
+        if self.images_next_to_recipes {
+            recipe_path.with_file_name(file_name)
+        } else {
+            Path::new(&self.image_dir).join(file_name)
+        }
+    }
+
+    pub(crate) fn search_index_path(&self) -> Option<PathBuf> {
+        self.search_index_pattern().map(PathBuf::from)
+    }
+
+    fn search_index_pattern(&self) -> Option<&String> {
+        self.search_index_path
+            .as_ref()
+            .filter(|pattern| !pattern.is_empty())
+    }
+
+    pub(crate) fn recipe_path(&self, slug: &str, tag: &str, ext: &OsStr) -> PathBuf {
+        expand(
+            &self.recipe_path,
+            &[
+                (SLUG_PLACEHOLDER, slug),
+                (TAG_PLACEHOLDER, tag),
+                (EXT_PLACEHOLDER, &ext.to_string_lossy()),
+            ],
+        )
+    }
+
+    pub(crate) fn tag_path(&self, slug: &str, ext: &OsStr) -> PathBuf {
+        expand(
+            &self.tag_path,
+            &[
+                (SLUG_PLACEHOLDER, slug),
+                (EXT_PLACEHOLDER, &ext.to_string_lossy()),
+            ],
+        )
+    }
+}
+
+/// Returns an error unless `pattern` is a relative path without `.` or `..`.
+pub(crate) fn validate_relative_path(pattern: &str) -> Result<()> {
+    let path = Path::new(pattern);
+    if path
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        Ok(())
+    } else {
+        Err(Error::InvalidPathPattern(
+            pattern.into(),
+            "must be a relative path without '.' or '..'",
+        ))
+    }
+}
+
+/// Returns the absolute URL of the output file at `path` (percent-encoded),
+/// with `site_url` being the URL of the output directory.
+pub(crate) fn absolute_url(site_url: &str, path: &Path) -> String {
+    let mut url = site_url.trim_end_matches('/').to_string();
+    url.push('/');
+    let path = path.to_string_lossy().replace('\\', "/");
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            url.push(char::from(byte));
+        } else {
+            url.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    url
+}
+
+/// Returns the relative URL of the output directory as seen from the file at `path`.
+pub(crate) fn base_url(path: &Path) -> String {
+    let depth = path.components().count().saturating_sub(1);
+    if depth == 0 {
+        ".".into()
+    } else {
+        vec![".."; depth].join("/")
+    }
+}
+
+// Empty path segments (e.g. of untagged recipes) are removed.
+fn expand(pattern: &str, replacements: &[(&str, &str)]) -> PathBuf {
+    pattern
+        .split('/')
+        .map(|segment| {
+            replacements
+                .iter()
+                .fold(segment.to_string(), |segment, (placeholder, value)| {
+                    segment.replace(placeholder, value)
+                })
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let layout = Layout::default();
+        layout.validate().unwrap();
+        let ext = OsStr::new("html");
+        let recipe_path = layout.recipe_path("slug", "tag", ext);
+        assert_eq!(recipe_path, Path::new("recipes/slug.html"));
+        assert_eq!(
+            layout.image_path(&recipe_path, "slug", OsStr::new("jpg")),
+            Path::new("images/slug.jpg")
+        );
+        assert_eq!(layout.index_path(ext), Path::new("index.html"));
+        assert_eq!(layout.ingredients_path(ext), Path::new("ingredients.html"));
+        assert_eq!(
+            layout.gallery_path(&recipe_path, "slug", "step.jpg"),
+            Path::new("images/slug/step.jpg")
+        );
+        assert_eq!(layout.tag_path("tag", ext), Path::new("tags/tag.html"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let layout = Layout {
+            recipe_path: "{tag}/{slug}/index.{ext}".into(),
+            images_next_to_recipes: true,
+            ..Layout::default()
+        };
+        layout.validate().unwrap();
+        let ext = OsStr::new("html");
+        let recipe_path = layout.recipe_path("slug", "tag", ext);
+        assert_eq!(recipe_path, Path::new("tag/slug/index.html"));
+        assert_eq!(
+            layout.image_path(&recipe_path, "slug", OsStr::new("jpg")),
+            Path::new("tag/slug/slug.jpg")
+        );
+        assert_eq!(
+            layout.gallery_path(&recipe_path, "slug", "step.jpg"),
+            Path::new("tag/slug/slug-step.jpg")
+        );
+        assert_eq!(
+            layout.recipe_path("slug", "", ext),
+            Path::new("slug/index.html")
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut layout = Layout {
+            recipe_path: "recipes/{tag}.html".into(),
+            ..Layout::default()
+        };
+        assert!(layout.validate().is_err());
+        layout.recipe_path = "../{slug}.html".into();
+        assert!(layout.validate().is_err());
+        layout.recipe_path = "/{slug}.html".into();
+        assert!(layout.validate().is_err());
+        layout.recipe_path = "{slug}.html".into();
+        layout.tag_path = "tags.html".into();
+        assert!(layout.validate().is_err());
+        layout.tag_path = "tags/{slug}.html".into();
+        layout.search_index_path = Some("../search.json".into());
+        assert!(layout.validate().is_err());
+    }
+
+    #[test]
+    fn test_base_url() {
+        assert_eq!(base_url(Path::new("index.html")), ".");
+        assert_eq!(base_url(Path::new("recipes/slug.html")), "..");
+        assert_eq!(base_url(Path::new("recipes/slug/index.html")), "../..");
+    }
+
+    #[test]
+    fn test_absolute_url() {
+        let url = "https://example.com/recipes/";
+        assert_eq!(
+            absolute_url(url, Path::new("")),
+            "https://example.com/recipes/"
+        );
+        assert_eq!(
+            absolute_url(url, Path::new("recipes/Crème brûlée.html")),
+            "https://example.com/recipes/recipes/Cr%C3%A8me%20br%C3%BBl%C3%A9e.html"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::Result;
+
+use super::image::Image;
+
+pub const MANIFEST_FILE_NAME: &str = ".manifest.json";
+
+const MANIFEST_VERSION: u32 = 3;
+
+#[derive(Default)]
+pub(crate) struct Hasher(Sha256);
+
+impl Hasher {
+    pub fn update<D: AsRef<[u8]>>(&mut self, data: D) {
+        let data = data.as_ref();
+        // prefix data with its length to keep consecutive updates unambiguous
+        self.0.update((data.len() as u64).to_le_bytes());
+        self.0.update(data);
+    }
+
+    pub fn update_file(&mut self, path: &Path) -> io::Result<()> {
+        self.update(fs::read(path)?);
+        Ok(())
+    }
+
+    pub fn finish(self) -> String {
+        self.0
+            .finalize()
+            .iter()
+            .fold(String::new(), |mut hex, byte| {
+                write!(hex, "{byte:02x}").unwrap();
+                hex
+            })
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub(crate) struct Entry {
+    pub hash: String,
+    pub files: Vec<PathBuf>,
+    pub image: Option<Image>,
+    pub gallery: Vec<Image>,
+}
+
+/// Record of a previous build, stored in the output directory.
+///
+/// All file paths are relative to the output directory.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct Manifest {
+    version: u32,
+    pub template: String,
+    pub recipes: BTreeMap<String, Entry>,
+    pub pages: Vec<PathBuf>,
+    pub static_hash: String,
+    pub static_files: Vec<PathBuf>,
+}
+
+impl Manifest {
+    pub fn new(template: String) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            template,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `None` if there is no manifest or it cannot be read, which
+    /// results in a full rebuild.
+    pub fn load(output_dir: &Path) -> Result<Option<Self>> {
+        let data = match fs::read(output_dir.join(MANIFEST_FILE_NAME)) {
+            Ok(data) => data,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        Ok(serde_json::from_slice(&data)
+            .ok()
+            .filter(|manifest: &Self| manifest.version == MANIFEST_VERSION))
+    }
+
+    pub fn store(&self, output_dir: &Path) -> io::Result<()> {
+        let file = fs::File::create(output_dir.join(MANIFEST_FILE_NAME))?;
+        serde_json::to_writer_pretty(file, self).map_err(io::Error::from)
+    }
+
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.recipes
+            .values()
+            .flat_map(|entry| &entry.files)
+            .chain(&self.pages)
+            .chain(&self.static_files)
+            .map(PathBuf::as_path)
+    }
+
+    /// Removes all files of the `previous` manifest that are not part of this one.
+    pub fn remove_stale_files(&self, previous: &Self, output_dir: &Path) -> io::Result<()> {
+        let files: HashSet<_> = self.files().collect();
+        for path in previous.files().filter(|path| !files.contains(path)) {
+            match fs::remove_file(output_dir.join(path)) {
+                Err(error) if error.kind() != io::ErrorKind::NotFound => return Err(error),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_hasher() {
+        let mut hasher = Hasher::default();
+        hasher.update("ab");
+        hasher.update("c");
+        let mut other = Hasher::default();
+        other.update("a");
+        other.update("bc");
+        let hash = hasher.finish();
+        assert_eq!(hash.len(), 64);
+        assert_ne!(hash, other.finish());
+    }
+
+    #[test]
+    fn test_store_and_load() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path();
+        assert!(Manifest::load(output_dir)?.is_none());
+        let mut manifest = Manifest::new("template".into());
+        manifest.recipes.insert(
+            "recipe".into(),
+            Entry {
+                hash: "hash".into(),
+                files: vec!["recipes/recipe.html".into()],
+                image: None,
+                gallery: Vec::new(),
+            },
+        );
+        manifest.store(output_dir)?;
+        let manifest = Manifest::load(output_dir)?.unwrap();
+        assert_eq!(manifest.template, "template");
+        assert_eq!(manifest.recipes["recipe"].hash, "hash");
+        fs::write(output_dir.join(MANIFEST_FILE_NAME), "corrupt")?;
+        assert!(Manifest::load(output_dir)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_stale_files() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let output_dir = temp_dir.path();
+        fs::write(output_dir.join("kept"), "")?;
+        fs::write(output_dir.join("stale"), "")?;
+        let mut previous = Manifest::new("template".into());
+        previous.pages = vec!["kept".into(), "stale".into(), "missing".into()];
+        let mut manifest = Manifest::new("template".into());
+        manifest.pages = vec!["kept".into()];
+        manifest.remove_stale_files(&previous, output_dir)?;
+        assert!(output_dir.join("kept").exists());
+        assert!(!output_dir.join("stale").exists());
+        Ok(())
+    }
+}
@@ -0,0 +1,1688 @@
+mod cookbook;
+pub mod epub;
+pub mod feed;
+pub mod image;
+pub mod layout;
+pub(crate) mod manifest;
+mod references;
+pub mod related;
+pub mod search;
+pub mod sitemap;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsString,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use rayon::prelude::*;
+use serde::Serialize;
+use serde_json::Value;
+use tera::Context;
+
+use crate::{
+    error::{self, Error, Result},
+    recipe::{
+        Recipe,
+        cost::{CostedRecipe, Prices},
+        directory::Directory,
+        ingredient::Ingredient,
+        redirects::Redirects,
+        reference::find_references,
+        synonyms::Synonyms,
+    },
+    template,
+    util::{copy_dir, list_files, timestamp},
+};
+
+use self::{
+    feed::FeedOptions,
+    image::{Image, ImageOptions},
+    layout::{Layout, base_url},
+    manifest::{Entry, Hasher, Manifest},
+    search::SearchIndex,
+};
+
+/// Filter of output file names, its debug format is part of the build
+/// identity (output paths change with it).
+pub trait TextFilter: fmt::Debug {
+    fn filter<S: AsRef<str>>(&self, text: S) -> String;
+}
+
+const STATIC_DIR: &str = "static";
+
+// number of ingredients listed in `Stats::common_ingredients`
+const COMMON_INGREDIENT_COUNT: usize = 10;
+
+// number of related recipes of a recipe page
+const RELATED_RECIPE_COUNT: usize = 5;
+
+/// When a recipe was added (the `Added` metadata or the modification time
+/// of its file) and last changed, as UTC timestamps.
+#[derive(Clone, Debug, Serialize)]
+struct Dates {
+    added: String,
+    updated: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IndexEntry {
+    pub title: String,
+    pub path: PathBuf,
+    pub tags: Vec<String>,
+    pub image_path: Option<PathBuf>,
+    pub image: Option<Image>,
+    pub summary: Option<String>,
+    #[serde(flatten)]
+    pub dates: Dates,
+    pub recipe: CostedRecipe,
+}
+
+#[derive(Debug, Serialize)]
+struct IngredientCount<'a> {
+    name: &'a str,
+    /// Number of recipes using the ingredient
+    count: usize,
+}
+
+/// Statistics of the whole recipe collection.
+#[derive(Debug, Serialize)]
+struct Stats<'a> {
+    recipe_count: usize,
+    ingredient_count: usize,
+    tag_counts: BTreeMap<&'a str, usize>,
+    common_ingredients: Vec<IngredientCount<'a>>,
+}
+
+impl<'a> Stats<'a> {
+    /// Ingredients are counted by their identifier (see [`Synonyms::id`])
+    /// and named by their canonical name or else the first spelling.
+    fn new(entries: &'a [IndexEntry], synonyms: &'a Synonyms) -> Self {
+        let mut tag_counts = BTreeMap::new();
+        let mut ingredient_counts = HashMap::new();
+        let mut ingredient_count = 0;
+        for entry in entries {
+            for tag in &entry.tags {
+                *tag_counts.entry(tag.as_str()).or_default() += 1;
+            }
+            let ingredients = &entry.recipe.ingredients;
+            ingredient_count += ingredients.count();
+            let ids: HashMap<_, _> = ingredients
+                .iter()
+                .map(|ingredient| (synonyms.id(&ingredient.name), ingredient.name.as_str()))
+                .collect();
+            for (id, name) in ids {
+                let name = synonyms.canonical_name(name).unwrap_or(name);
+                ingredient_counts.entry(id).or_insert((name, 0)).1 += 1;
+            }
+        }
+        let mut common_ingredients: Vec<_> = ingredient_counts
+            .into_values()
+            .map(|(name, count)| IngredientCount { name, count })
+            .collect();
+        common_ingredients.sort_by(|a, b| b.count.cmp(&a.count).then(a.name.cmp(b.name)));
+        common_ingredients.truncate(COMMON_INGREDIENT_COUNT);
+        Self {
+            recipe_count: entries.len(),
+            ingredient_count,
+            tag_counts,
+            common_ingredients,
+        }
+    }
+}
+
+/// Recipes using an ingredient, for the ingredients page.
+#[derive(Debug, Serialize)]
+struct IndexedIngredient<'a> {
+    name: &'a str,
+    recipes: Vec<RecipeLink>,
+}
+
+/// Link to a recipe from another page.
+#[derive(Debug, Serialize)]
+struct RecipeLink {
+    title: String,
+    /// Path relative to the output directory
+    path: PathBuf,
+    /// URL relative to the linking page, like the URLs of `references`
+    url: String,
+}
+
+impl RecipeLink {
+    fn new(title: String, path: PathBuf, base_url: &str) -> Self {
+        let url = format!("{base_url}/{}", path.to_str().unwrap());
+        Self { title, path, url }
+    }
+}
+
+/// Related recipes and recipes referencing a recipe.
+#[derive(Debug, Default, Serialize)]
+struct RecipeLinks {
+    related: Vec<RecipeLink>,
+    backlinks: Vec<RecipeLink>,
+}
+
+/// Old output path of a renamed recipe.
+#[derive(Debug, Serialize)]
+struct Redirect {
+    path: PathBuf,
+    target: PathBuf,
+    title: String,
+}
+
+struct Source {
+    directory: Directory,
+    recipe: CostedRecipe,
+    image_file_name: Option<OsString>,
+    gallery: Vec<OsString>,
+    captions: BTreeMap<String, String>,
+    dates: Dates,
+    hash: String,
+    /// Output file name without extension
+    name: String,
+    /// Directory names of referenced recipes by title
+    references: BTreeMap<String, OsString>,
+    /// Ingredients including those of referenced recipes, if expanded
+    shopping_list: Option<Vec<Ingredient>>,
+}
+
+pub struct Generator<F> {
+    engine: template::Engine,
+    image_file_exts: Vec<OsString>,
+    output_file_ext: OsString,
+    file_name_filter: F,
+    /// Ignore the manifest of a previous build and render everything.
+    pub clean: bool,
+    pub layout: Layout,
+    pub image_options: ImageOptions,
+    /// Absolute URL of the output directory, required for the feed
+    pub site_url: Option<String>,
+    /// Name of the site for link previews
+    pub site_name: Option<String>,
+    pub feed: Option<FeedOptions>,
+    /// Include the ingredients of referenced recipes in shopping lists
+    pub expand_references: bool,
+    /// Ingredient name variants, e.g. plural forms, to group ingredients by
+    pub synonyms: Synonyms,
+    /// Ingredient prices to estimate the cost of recipes (`recipe.cost` in
+    /// templates)
+    pub prices: Option<Prices>,
+}
+
+impl<F> Generator<F> {
+    pub fn new(
+        engine: template::Engine,
+        image_file_exts: Vec<OsString>,
+        output_file_ext: OsString,
+        file_name_filter: F,
+    ) -> Self {
+        Self {
+            engine,
+            image_file_exts,
+            output_file_ext,
+            file_name_filter,
+            clean: false,
+            layout: Layout::default(),
+            image_options: ImageOptions::default(),
+            site_url: None,
+            site_name: None,
+            feed: None,
+            expand_references: false,
+            synonyms: Synonyms::default(),
+            prices: None,
+        }
+    }
+}
+
+impl<F: TextFilter + Sync> Generator<F> {
+    /// Renders all recipes of `recipe_dir` into `output_dir`.
+    ///
+    /// Recipes (and images) whose inputs did not change since the previous
+    /// build are skipped, outputs of removed recipes are deleted.
+    pub fn generate(&self, recipe_dir: &Path, output_dir: &Path) -> Result<()> {
+        self.engine.require_template(template::RECIPE_NAME)?;
+        self.layout.validate()?;
+        if let Some(feed) = &self.feed {
+            layout::validate_relative_path(&feed.path)?;
+        }
+        let previous = Manifest::load(output_dir)?.unwrap_or_default();
+        let mut hasher = Hasher::default();
+        self.engine.hash_into(&mut hasher)?;
+        hasher.update(format!("{:?}", self.layout));
+        hasher.update(format!("{:?}", self.file_name_filter));
+        hasher.update(format!("{:?} {:?}", self.site_url, self.site_name));
+        hasher.update(format!("{:?}", self.expand_references));
+        hasher.update(format!("{:?}", self.synonyms));
+        let mut manifest = Manifest::new(hasher.finish());
+        let sources = self.load_sources(recipe_dir)?;
+        let redirects = self.find_redirects(recipe_dir, &sources)?;
+        let tag_paths = self.tag_paths(&sources)?;
+        let mut index =
+            self.render_recipes(sources, &tag_paths, output_dir, &previous, &mut manifest)?;
+        index.sort_by(|a, b| a.title.cmp(&b.title));
+        if let Some(path) = self.layout.search_index_path() {
+            self.write_search_index(&index, &path, output_dir)?;
+            manifest.pages.push(path);
+        }
+        if let Some(path) = self.feed_path() {
+            self.write_feed(&index, &path, output_dir)?;
+            manifest.pages.push(path);
+        }
+        let ingredients_path = self
+            .engine
+            .has_ingredients_template()
+            .then(|| self.layout.ingredients_path(&self.output_file_ext));
+        if let Some(path) = &ingredients_path {
+            self.render_ingredients(&index, &tag_paths, path, output_dir)?;
+            manifest.pages.push(path.clone());
+        }
+        let index_path = self
+            .engine
+            .has_index_template()
+            .then(|| self.layout.index_path(&self.output_file_ext));
+        if let Some(path) = &index_path {
+            self.render_index(
+                &index,
+                &redirects,
+                &tag_paths,
+                ingredients_path.as_deref(),
+                path,
+                output_dir,
+            )?;
+            manifest.pages.push(path.clone());
+        }
+        if self.engine.has_tag_template() {
+            self.render_tags(&index, &tag_paths, output_dir, &mut manifest)?;
+        }
+        if let Some(site_url) = &self.site_url {
+            let sitemap = sitemap::render(&index, index_path.as_deref(), &tag_paths, site_url);
+            let path = PathBuf::from(sitemap::SITEMAP_PATH);
+            let mut file = create_file(&output_dir.join(&path))?;
+            io::Write::write_all(&mut file, sitemap.as_bytes())?;
+            manifest.pages.push(path);
+        }
+        self.render_pages(&index, &tag_paths, output_dir, &mut manifest)?;
+        if self.engine.has_redirect_template() {
+            self.render_redirects(&redirects, output_dir, &mut manifest)?;
+        }
+        self.copy_static_files(output_dir, &previous, &mut manifest)?;
+        manifest.remove_stale_files(&previous, output_dir)?;
+        manifest.store(output_dir)?;
+        Ok(())
+    }
+
+    fn copy_static_files(
+        &self,
+        output_dir: &Path,
+        previous: &Manifest,
+        manifest: &mut Manifest,
+    ) -> Result<()> {
+        let static_path = self.engine.static_path();
+        if !static_path.exists() {
+            return Ok(());
+        }
+        let mut hasher = Hasher::default();
+        template::hash_files(static_path, &mut hasher)?;
+        manifest.static_hash = hasher.finish();
+        manifest.static_files = list_files(static_path)?
+            .into_iter()
+            .map(|path| Path::new(STATIC_DIR).join(path))
+            .collect();
+        let is_up_to_date = !self.clean
+            && manifest.static_hash == previous.static_hash
+            && all_exist(output_dir, &manifest.static_files);
+        if !is_up_to_date {
+            copy_dir(static_path, output_dir.join(STATIC_DIR))?;
+        }
+        Ok(())
+    }
+
+    /// Finds the old output paths of renamed recipes.
+    ///
+    /// Recipes with an explicit slug are skipped, because renaming does not
+    /// change their output path.
+    fn find_redirects(&self, recipe_dir: &Path, sources: &[Source]) -> Result<Vec<Redirect>> {
+        let log = Redirects::load(recipe_dir)?;
+        let sources_by_dir: HashMap<_, _> = sources
+            .iter()
+            .map(|source| (source.directory.base_name().to_string_lossy(), source))
+            .collect();
+        let mut names: HashSet<_> = sources.iter().map(|source| source.name.clone()).collect();
+        let mut redirects = Vec::new();
+        for old_dir_name in log.names() {
+            let Some(source) = log
+                .resolve(old_dir_name)
+                .and_then(|dir_name| sources_by_dir.get(dir_name))
+            else {
+                continue;
+            };
+            let name = self.file_name_filter.filter(old_dir_name);
+            if source.recipe.metadata.slug.is_none() && names.insert(name.clone()) {
+                redirects.push(Redirect {
+                    path: self.recipe_path(&name, &source.recipe),
+                    target: self.recipe_path(&source.name, &source.recipe),
+                    title: source.recipe.title.clone(),
+                });
+            }
+        }
+        Ok(redirects)
+    }
+
+    fn recipe_path(&self, name: &str, recipe: &Recipe) -> PathBuf {
+        let tag = recipe
+            .metadata
+            .tags
+            .first()
+            .map(|tag| self.file_name_filter.filter(tag))
+            .unwrap_or_default();
+        self.layout.recipe_path(name, &tag, &self.output_file_ext)
+    }
+
+    fn render_index(
+        &self,
+        entries: &[IndexEntry],
+        redirects: &[Redirect],
+        tag_paths: &BTreeMap<String, PathBuf>,
+        ingredients_path: Option<&Path>,
+        path: &Path,
+        output_dir: &Path,
+    ) -> Result<()> {
+        let mut context = Context::new();
+        context.insert("recipes", entries);
+        context.insert("tags", &get_distinct_tags(entries));
+        context.insert("tag_paths", tag_paths);
+        context.insert("stats", &Stats::new(entries, &self.synonyms));
+        context.insert("redirects", redirects);
+        context.insert("ingredients_path", &ingredients_path);
+        context.insert("search_index_path", &self.layout.search_index_path());
+        context.insert("feed_path", &self.feed_path());
+        context.insert("url", &self.url(path));
+        context.insert("base_url", &base_url(path));
+        let file = create_file(&output_dir.join(path))?;
+        self.engine.render_index(context, file)
+    }
+
+    /// Renders the ingredients page listing all ingredients with the recipes
+    /// using them.
+    fn render_ingredients(
+        &self,
+        entries: &[IndexEntry],
+        tag_paths: &BTreeMap<String, PathBuf>,
+        path: &Path,
+        output_dir: &Path,
+    ) -> Result<()> {
+        let mut context = Context::new();
+        context.insert(
+            "ingredients",
+            &self.ingredient_index(entries, &base_url(path)),
+        );
+        context.insert("tags", &get_distinct_tags(entries));
+        context.insert("tag_paths", tag_paths);
+        context.insert("path", path);
+        context.insert("url", &self.url(path));
+        context.insert("base_url", &base_url(path));
+        let file = create_file(&output_dir.join(path))?;
+        self.engine.render_ingredients(context, file)
+    }
+
+    /// Lists all ingredients in alphabetical order with the recipes using
+    /// them. Ingredients are grouped by their synonyms and named by their
+    /// canonical name or else the first spelling, ingredients referencing
+    /// other recipes are skipped. Links are relative to `base_url`.
+    fn ingredient_index<'a>(
+        &'a self,
+        entries: &'a [IndexEntry],
+        base_url: &str,
+    ) -> Vec<IndexedIngredient<'a>> {
+        let mut ingredients: BTreeMap<_, IndexedIngredient> = BTreeMap::new();
+        for entry in entries {
+            for ingredient in entry.recipe.ingredients.iter() {
+                let name = &ingredient.name;
+                if find_references(name).next().is_some() {
+                    continue;
+                }
+                let indexed = ingredients
+                    .entry(self.synonyms.id(name))
+                    .or_insert_with(|| IndexedIngredient {
+                        name: self.synonyms.canonical_name(name).unwrap_or(name),
+                        recipes: Vec::new(),
+                    });
+                if indexed
+                    .recipes
+                    .last()
+                    .is_none_or(|recipe| recipe.path != entry.path)
+                {
+                    indexed.recipes.push(RecipeLink::new(
+                        entry.title.clone(),
+                        entry.path.clone(),
+                        base_url,
+                    ));
+                }
+            }
+        }
+        ingredients.into_values().collect()
+    }
+
+    fn write_search_index(
+        &self,
+        entries: &[IndexEntry],
+        path: &Path,
+        output_dir: &Path,
+    ) -> Result<()> {
+        let recipes = entries
+            .iter()
+            .map(|entry| (&entry.recipe.recipe, entry.path.as_path()));
+        let index = SearchIndex::new(recipes, self.engine.language.as_deref(), &self.synonyms);
+        let mut file = create_file(&output_dir.join(path))?;
+        io::Write::write_all(&mut file, index.to_json().as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the absolute URL of the output file at `path` if a site URL is set.
+    fn url(&self, path: &Path) -> Option<String> {
+        self.site_url
+            .as_deref()
+            .map(|site_url| layout::absolute_url(site_url, path))
+    }
+
+    /// Returns the output path of the feed if it is configured and a site URL is set.
+    fn feed_path(&self) -> Option<PathBuf> {
+        self.feed
+            .as_ref()
+            .filter(|_| self.site_url.is_some())
+            .map(|feed| PathBuf::from(&feed.path))
+    }
+
+    fn write_feed(&self, entries: &[IndexEntry], path: &Path, output_dir: &Path) -> Result<()> {
+        let (Some(feed), Some(site_url)) = (&self.feed, &self.site_url) else {
+            return Ok(());
+        };
+        let mut file = create_file(&output_dir.join(path))?;
+        io::Write::write_all(&mut file, feed::render(entries, feed, site_url).as_bytes())?;
+        Ok(())
+    }
+
+    fn load_source(&self, directory: Directory) -> Result<Source> {
+        let mut hasher = Hasher::default();
+        let recipe_path = directory.recipe_path();
+        hasher.update_file(&recipe_path)?;
+        let recipe = directory.load()?;
+        let cost = self
+            .prices
+            .as_ref()
+            .map(|prices| prices.estimate(&recipe, &self.synonyms));
+        hasher.update(format!("{cost:?}"));
+        let modified = fs::metadata(&recipe_path)?.modified()?;
+        let added = recipe
+            .metadata
+            .added
+            .map_or(modified, |date| date.to_system_time());
+        let dates = Dates {
+            added: timestamp(added),
+            updated: timestamp(added.max(modified)),
+        };
+        hasher.update(format!("{dates:?}"));
+        let image_file_name = directory.image_file_name(&self.image_file_exts)?;
+        let gallery = directory.gallery_file_names(&self.image_file_exts)?;
+        let captions = directory.load_captions()?;
+        hasher.update(format!("{:?}", self.image_options));
+        if let Some(image_file_name) = &image_file_name {
+            hasher.update(image_file_name.as_encoded_bytes());
+            hasher.update_file(&directory.path().join(image_file_name))?;
+        }
+        for file_name in &gallery {
+            hasher.update(file_name.as_encoded_bytes());
+            hasher.update_file(&directory.gallery_path().join(file_name))?;
+        }
+        hasher.update(format!("{captions:?}"));
+        let name = match &recipe.metadata.slug {
+            Some(slug) => self.file_name_filter.filter(slug),
+            None => self
+                .file_name_filter
+                .filter(directory.base_name().to_string_lossy()),
+        };
+        Ok(Source {
+            directory,
+            recipe: CostedRecipe { recipe, cost },
+            image_file_name,
+            gallery,
+            captions,
+            dates,
+            hash: hasher.finish(),
+            name,
+            references: BTreeMap::new(),
+            shopping_list: None,
+        })
+    }
+
+    /// Loads all recipes in parallel, sorted by directory name.
+    ///
+    /// Errors of all recipes are collected instead of aborting on the first one.
+    /// References between recipes are resolved afterwards.
+    fn load_sources(&self, recipe_dir: &Path) -> Result<Vec<Source>> {
+        let mut directories = Directory::list_all(recipe_dir)?;
+        directories.sort_by(|a, b| a.base_name().cmp(b.base_name()));
+        let mut sources = error::collect(
+            directories
+                .into_par_iter()
+                .map(|directory| self.load_source(directory))
+                .collect::<Vec<_>>(),
+        )?;
+        let mut names = HashMap::new();
+        let collisions = sources.iter().filter_map(|source| {
+            names
+                .insert(&source.name, source.directory.base_name())
+                .map(|other| {
+                    Err(Error::OutputNameCollision(
+                        source.name.clone(),
+                        other.to_string_lossy().into(),
+                        source.directory.base_name().to_string_lossy().into(),
+                    ))
+                })
+        });
+        error::collect::<(), _>(collisions.collect::<Vec<_>>())?;
+        references::resolve(
+            recipe_dir,
+            &mut sources,
+            self.expand_references,
+            &self.synonyms,
+        )?;
+        Ok(sources)
+    }
+
+    fn render_recipes(
+        &self,
+        sources: Vec<Source>,
+        tag_paths: &BTreeMap<String, PathBuf>,
+        output_dir: &Path,
+        previous: &Manifest,
+        manifest: &mut Manifest,
+    ) -> Result<Vec<IndexEntry>> {
+        let is_template_up_to_date = !self.clean && manifest.template == previous.template;
+        let recipe_paths: HashMap<_, _> = sources
+            .iter()
+            .map(|source| {
+                let path = self.recipe_path(&source.name, &source.recipe);
+                (source.directory.base_name().to_owned(), path)
+            })
+            .collect();
+        let links = recipe_links(&sources, &recipe_paths, &self.synonyms);
+        let results: Vec<_> = sources
+            .into_par_iter()
+            .zip(links)
+            .map(|(source, links)| {
+                let recipe_path = self.recipe_path(&source.name, &source.recipe);
+                let key = source.directory.base_name().to_string_lossy().into_owned();
+                // the page depends on the links to other recipes as well
+                let mut hasher = Hasher::default();
+                hasher.update(&source.hash);
+                hasher.update(format!("{links:?}"));
+                let hash = hasher.finish();
+                let previous_entry = previous
+                    .recipes
+                    .get(&key)
+                    .filter(|entry| !self.clean && entry.hash == hash);
+                let (image, gallery) =
+                    self.process_images(&source, &recipe_path, previous_entry, output_dir)?;
+                let mut files = vec![recipe_path.clone()];
+                files.extend(image.iter().chain(&gallery).flat_map(Image::files));
+                let entry = Entry {
+                    hash,
+                    files,
+                    image,
+                    gallery,
+                };
+                let is_unchanged = previous_entry.is_some_and(|previous| *previous == entry);
+                let image_path = entry.image.as_ref().map(|image| image.path.clone());
+                let summary = source.recipe.summary();
+                if !(is_unchanged && is_template_up_to_date && all_exist(output_dir, &entry.files))
+                {
+                    let mut context = Context::new();
+                    context.insert("recipe", &source.recipe);
+                    context.insert("path", recipe_path.to_str().unwrap());
+                    context.insert("added", &source.dates.added);
+                    context.insert("updated", &source.dates.updated);
+                    context.insert(
+                        "image_path",
+                        &image_path.as_deref().map(|path| path.to_str().unwrap()),
+                    );
+                    let base_url = base_url(&recipe_path);
+                    let (image, gallery) = image_values(&source, &entry, &base_url);
+                    context.insert("image", &image);
+                    context.insert("gallery", &gallery);
+                    context.insert("base_url", &base_url);
+                    context.insert("url", &self.url(&recipe_path));
+                    context.insert("summary", &summary);
+                    context.insert(
+                        "image_url",
+                        &entry.image.as_ref().and_then(|image| self.url(&image.path)),
+                    );
+                    context.insert("site_name", &self.site_name);
+                    // only the paths of its own tags, so that the page does not depend on other recipes
+                    let recipe_tag_paths: BTreeMap<_, _> = source
+                        .recipe
+                        .metadata
+                        .tags
+                        .iter()
+                        .filter_map(|tag| tag_paths.get_key_value(tag.as_str()))
+                        .collect();
+                    context.insert("tag_paths", &recipe_tag_paths);
+                    let references: BTreeMap<_, _> = source
+                        .references
+                        .iter()
+                        .map(|(title, name)| {
+                            let path = recipe_paths[name].to_str().unwrap();
+                            (title, format!("{base_url}/{path}"))
+                        })
+                        .collect();
+                    context.insert("references", &references);
+                    context.insert("shopping_list", &source.shopping_list);
+                    context.insert("related", &links.related);
+                    context.insert("backlinks", &links.backlinks);
+                    let file = create_file(&output_dir.join(&recipe_path))?;
+                    self.engine.render_recipe(context, file)?;
+                }
+                let recipe = source.recipe;
+                let mut tags = recipe.metadata.tags.clone();
+                tags.sort();
+                let index_entry = IndexEntry {
+                    title: recipe.title.clone(),
+                    path: recipe_path,
+                    tags,
+                    image_path,
+                    image: entry.image.clone(),
+                    summary,
+                    dates: source.dates,
+                    recipe,
+                };
+                Ok((key, entry, index_entry))
+            })
+            .collect();
+        let mut index_entries = Vec::new();
+        for (key, entry, index_entry) in error::collect(results)? {
+            manifest.recipes.insert(key, entry);
+            index_entries.push(index_entry);
+        }
+        Ok(index_entries)
+    }
+
+    /// Processes the main image and the gallery of a recipe whose output is at `recipe_path`.
+    fn process_images(
+        &self,
+        source: &Source,
+        recipe_path: &Path,
+        previous_entry: Option<&Entry>,
+        output_dir: &Path,
+    ) -> Result<(Option<Image>, Vec<Image>)> {
+        let image = match &source.image_file_name {
+            Some(file_name) => {
+                let ext = Path::new(file_name).extension().unwrap();
+                Some(self.process_image(
+                    &source.directory.path().join(file_name),
+                    &self.layout.image_path(recipe_path, &source.name, ext),
+                    previous_entry.and_then(|entry| entry.image.as_ref()),
+                    output_dir,
+                )?)
+            }
+            None => None,
+        };
+        let gallery = source
+            .gallery
+            .iter()
+            .enumerate()
+            .map(|(i, file_name)| {
+                let file_name = Path::new(file_name);
+                let output_name = format!(
+                    "{}.{}",
+                    self.file_name_filter
+                        .filter(file_name.file_stem().unwrap().to_string_lossy()),
+                    file_name.extension().unwrap().to_string_lossy()
+                );
+                self.process_image(
+                    &source.directory.gallery_path().join(file_name),
+                    &self
+                        .layout
+                        .gallery_path(recipe_path, &source.name, &output_name),
+                    previous_entry.and_then(|entry| entry.gallery.get(i)),
+                    output_dir,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((image, gallery))
+    }
+
+    /// Processes the image at `source` unless the `cached` result of the previous build still exists.
+    fn process_image(
+        &self,
+        source: &Path,
+        path: &Path,
+        cached: Option<&Image>,
+        output_dir: &Path,
+    ) -> Result<Image> {
+        // the output format might change the extension
+        let cached = cached.filter(|image| {
+            image.path.with_extension("") == path.with_extension("")
+                && all_exist(output_dir, &image.files())
+        });
+        match cached {
+            Some(image) => Ok(image.clone()),
+            None => image::process(source, path, output_dir, &self.image_options),
+        }
+    }
+
+    /// Renders a page per distinct tag listing all recipes with that tag.
+    fn render_tags(
+        &self,
+        entries: &[IndexEntry],
+        tag_paths: &BTreeMap<String, PathBuf>,
+        output_dir: &Path,
+        manifest: &mut Manifest,
+    ) -> Result<()> {
+        let tags = get_distinct_tags(entries);
+        for (tag, path) in tag_paths {
+            let tagged: Vec<_> = entries
+                .iter()
+                .filter(|entry| entry.tags.iter().any(|other| other == tag))
+                .collect();
+            let mut context = Context::new();
+            context.insert("tag", tag);
+            context.insert("recipes", &tagged);
+            context.insert("tags", &tags);
+            context.insert("tag_paths", tag_paths);
+            context.insert("path", path);
+            context.insert("url", &self.url(path));
+            context.insert("base_url", &base_url(path));
+            let file = create_file(&output_dir.join(path))?;
+            self.engine.render_tag(context, file)?;
+            manifest.pages.push(path.clone());
+        }
+        Ok(())
+    }
+
+    /// Renders the additional page templates, each with all recipes.
+    ///
+    /// The output path of a page is its template path within the pages directory.
+    fn render_pages(
+        &self,
+        entries: &[IndexEntry],
+        tag_paths: &BTreeMap<String, PathBuf>,
+        output_dir: &Path,
+        manifest: &mut Manifest,
+    ) -> Result<()> {
+        let tags = get_distinct_tags(entries);
+        let stats = Stats::new(entries, &self.synonyms);
+        for name in self.engine.page_names() {
+            let path = Path::new(name).strip_prefix(template::PAGES_DIR).unwrap();
+            let mut context = Context::new();
+            context.insert("recipes", entries);
+            context.insert("tags", &tags);
+            context.insert("tag_paths", tag_paths);
+            context.insert("stats", &stats);
+            context.insert("path", path);
+            context.insert("url", &self.url(path));
+            context.insert("base_url", &base_url(path));
+            let file = create_file(&output_dir.join(path))?;
+            self.engine.render_page(name, context, file)?;
+            manifest.pages.push(path.into());
+        }
+        Ok(())
+    }
+
+    /// Returns the output paths of the tag pages (empty without a tag template).
+    fn tag_paths(&self, sources: &[Source]) -> Result<BTreeMap<String, PathBuf>> {
+        let mut tag_paths = BTreeMap::new();
+        if !self.engine.has_tag_template() {
+            return Ok(tag_paths);
+        }
+        let mut tags_by_name: HashMap<_, &String> = HashMap::new();
+        for tag in sources
+            .iter()
+            .flat_map(|source| &source.recipe.metadata.tags)
+        {
+            let name = self.file_name_filter.filter(tag);
+            match tags_by_name.get(&name) {
+                Some(&other) if other != tag => {
+                    return Err(Error::TagNameCollision(name, other.clone(), tag.clone()));
+                }
+                Some(_) => {}
+                None => {
+                    tag_paths.insert(
+                        tag.clone(),
+                        self.layout.tag_path(&name, &self.output_file_ext),
+                    );
+                    tags_by_name.insert(name, tag);
+                }
+            }
+        }
+        Ok(tag_paths)
+    }
+
+    fn render_redirects(
+        &self,
+        redirects: &[Redirect],
+        output_dir: &Path,
+        manifest: &mut Manifest,
+    ) -> Result<()> {
+        for redirect in redirects {
+            let mut context = Context::new();
+            context.insert("title", &redirect.title);
+            context.insert("path", &redirect.target);
+            // the URL of the target
+            context.insert("url", &self.url(&redirect.target));
+            context.insert("base_url", &base_url(&redirect.path));
+            let file = create_file(&output_dir.join(&redirect.path))?;
+            self.engine.render_redirect(context, file)?;
+            manifest.pages.push(redirect.path.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Creates the file and all of its missing parent directories.
+fn create_file(path: &Path) -> io::Result<fs::File> {
+    fs::create_dir_all(path.parent().expect("invalid output file path"))?;
+    fs::File::create(path)
+}
+
+/// Returns the template values of the main image and the gallery images
+/// (with captions) of a recipe, with `srcset` URLs relative to `base_url`.
+fn image_values(source: &Source, entry: &Entry, base_url: &str) -> (Option<Value>, Vec<Value>) {
+    let image_value = |image: &Image| {
+        let mut value = serde_json::to_value(image).unwrap();
+        value["srcset"] = image.srcset(base_url).into();
+        value
+    };
+    let gallery = source
+        .gallery
+        .iter()
+        .zip(&entry.gallery)
+        .map(|(file_name, image)| {
+            let name = file_name.to_string_lossy();
+            let mut value = image_value(image);
+            value["name"] = name.as_ref().into();
+            value["caption"] = source.captions.get(name.as_ref()).cloned().into();
+            value
+        })
+        .collect();
+    (entry.image.as_ref().map(image_value), gallery)
+}
+
+/// Returns the related recipes (see [`related::related`]) and the recipes
+/// referencing it (sorted by title) for each source, with URLs relative to
+/// its recipe page.
+fn recipe_links(
+    sources: &[Source],
+    recipe_paths: &HashMap<OsString, PathBuf>,
+    synonyms: &Synonyms,
+) -> Vec<RecipeLinks> {
+    let path = |source: &Source| &recipe_paths[source.directory.base_name()];
+    let link = |source: &Source, from: &Source| {
+        let base_url = base_url(path(from));
+        RecipeLink::new(source.recipe.title.clone(), path(source).clone(), &base_url)
+    };
+    let recipes: Vec<_> = sources.iter().map(|source| &source.recipe.recipe).collect();
+    let mut links: Vec<_> = related::related(&recipes, RELATED_RECIPE_COUNT, synonyms)
+        .into_iter()
+        .enumerate()
+        .map(|(from, related)| RecipeLinks {
+            related: related
+                .into_iter()
+                .map(|index| link(&sources[index], &sources[from]))
+                .collect(),
+            backlinks: Vec::new(),
+        })
+        .collect();
+    let indices: HashMap<_, _> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| (source.directory.base_name(), index))
+        .collect();
+    for (index, source) in sources.iter().enumerate() {
+        let referenced: HashSet<_> = source
+            .references
+            .values()
+            .map(|name| indices[name.as_os_str()])
+            .collect();
+        for referenced in referenced.into_iter().filter(|&other| other != index) {
+            links[referenced]
+                .backlinks
+                .push(link(source, &sources[referenced]));
+        }
+    }
+    for links in &mut links {
+        links.backlinks.sort_by(|a, b| a.title.cmp(&b.title));
+    }
+    links
+}
+
+fn all_exist(output_dir: &Path, files: &[PathBuf]) -> bool {
+    files.iter().all(|path| output_dir.join(path).exists())
+}
+
+fn get_distinct_tags(entries: &[IndexEntry]) -> Vec<&String> {
+    let mut tags = HashSet::new();
+    for entry in entries {
+        for tag in &entry.tags {
+            if tags.contains(tag) {
+            } else {
+                tags.insert(tag);
+            }
+        }
+    }
+    let mut tags: Vec<_> = tags.into_iter().collect();
+    tags.sort();
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        fs::File,
+        io::{Cursor, Write},
+    };
+
+    use tempfile::tempdir;
+
+    use super::*;
+    use crate::recipe::cost::PRICES_FILE_NAME;
+
+    const IMAGE_DIR: &str = "images";
+    const RECIPE_DIR: &str = "recipes";
+
+    #[derive(Debug)]
+    struct FileNameFilter;
+
+    impl TextFilter for FileNameFilter {
+        fn filter<S: AsRef<str>>(&self, text: S) -> String {
+            text.as_ref().to_uppercase()
+        }
+    }
+
+    #[test]
+    fn test() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let static_dir = tpl_dir.join(STATIC_DIR);
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tpl_dir)?;
+        fs::create_dir_all(&static_dir)?;
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::create_dir_all(&output_dir)?;
+
+        // create recipe template
+        let mut file = File::create(tera_dir.join("recipe.html"))?;
+        writeln!(file, "title: {{{{ recipe.title }}}}")?;
+
+        // create index template
+        let mut file = File::create(tera_dir.join("index.html"))?;
+        writeln!(
+            file,
+            "{{% for r in recipes -%}}{{{{ r.title }}}}{{%- endfor %}}"
+        )?;
+
+        // default recipe
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- none";
+
+        // create and store recipe 1
+        let mut recipe1 = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe1.title = "recipe 1".into();
+        fs::create_dir(recipe_dir.join("recipe 1"))?;
+        let mut file = File::create(recipe_dir.join("recipe 1/recipe 1.recipe"))?;
+        writeln!(file, "{}", recipe1)?;
+
+        // create and store recipe 1 (2)
+        let mut recipe1_2 = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe1_2.title = "recipe 1".into();
+        fs::create_dir(recipe_dir.join("recipe 1 (2)"))?;
+        let mut file = File::create(recipe_dir.join("recipe 1 (2)/recipe 1 (2).recipe"))?;
+        writeln!(file, "{}", recipe1_2)?;
+
+        // create and store recipe 2
+        let mut recipe2 = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe2.title = "recipe 2".into();
+        fs::create_dir(recipe_dir.join("recipe 2"))?;
+        let mut file = File::create(recipe_dir.join("recipe 2/recipe 2.recipe"))?;
+        writeln!(file, "{}", recipe2)?;
+
+        // add image to recipe 2
+        File::create(recipe_dir.join("recipe 2/recipe 2.jpg"))?;
+
+        // create static content
+        File::create(static_dir.join("test.txt"))?;
+
+        // generate html
+        let mut context = Context::new();
+        context.insert(
+            "app",
+            &HashMap::from([("name", "name"), ("homepage", "homepage")]),
+        );
+        let mut engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+        engine.forced_context = Some(context);
+        let image_file_exts = vec!["jpg".into()];
+
+        let generator = Generator::new(engine, image_file_exts, "html".into(), FileNameFilter);
+        generator.generate(&recipe_dir, &output_dir)?;
+
+        // validate html
+        let recipe_output_dir = output_dir.join(RECIPE_DIR);
+        let image_output_dir = output_dir.join(IMAGE_DIR);
+        let static_output_dir = output_dir.join(STATIC_DIR);
+        let recipe1 = fs::read_to_string(recipe_output_dir.join("RECIPE 1.html"))?;
+        assert_eq!(recipe1, "title: recipe 1\n");
+        let recipe1_2 = fs::read_to_string(recipe_output_dir.join("RECIPE 1 (2).html"))?;
+        assert_eq!(recipe1_2, "title: recipe 1\n");
+        let recipe2 = fs::read_to_string(recipe_output_dir.join("RECIPE 2.html"))?;
+        assert_eq!(recipe2, "title: recipe 2\n");
+        assert!(image_output_dir.join("RECIPE 2.jpg").exists());
+        let index = fs::read_to_string(output_dir.join("index.html"))?;
+        assert_eq!(index, concat!("recipe 1", "recipe 1", "recipe 2", "\n"));
+        assert!(static_output_dir.join("test.txt").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::create_dir_all(&output_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ recipe.title }}")?;
+        fs::write(tera_dir.join("index.html"), "index")?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- none";
+        for title in ["recipe 1", "recipe 2"] {
+            let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+            recipe.title = title.into();
+            Directory::from_title(&recipe_dir, title)?.store(&recipe)?;
+        }
+        fs::write(recipe_dir.join("recipe 2/recipe 2.jpg"), "image")?;
+
+        let build = |clean| -> Result<()> {
+            let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+            let mut generator =
+                Generator::new(engine, vec!["jpg".into()], "html".into(), FileNameFilter);
+            generator.clean = clean;
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        build(false)?;
+        let recipe1_path = output_dir.join(RECIPE_DIR).join("RECIPE 1.html");
+        let recipe2_path = output_dir.join(RECIPE_DIR).join("RECIPE 2.html");
+        let image_path = output_dir.join(IMAGE_DIR).join("RECIPE 2.jpg");
+        assert!(output_dir.join(manifest::MANIFEST_FILE_NAME).exists());
+
+        // unchanged recipes are skipped
+        fs::write(&recipe1_path, "unchanged")?;
+        fs::write(&image_path, "unchanged")?;
+        build(false)?;
+        assert_eq!(fs::read_to_string(&recipe1_path)?, "unchanged");
+        assert_eq!(fs::read_to_string(&image_path)?, "unchanged");
+
+        // changed images are copied again
+        fs::write(recipe_dir.join("recipe 2/recipe 2.jpg"), "new image")?;
+        build(false)?;
+        assert_eq!(fs::read_to_string(&image_path)?, "new image");
+        assert_eq!(fs::read_to_string(&recipe1_path)?, "unchanged");
+
+        // template changes render all recipes again
+        fs::write(tera_dir.join("recipe.html"), "title: {{ recipe.title }}")?;
+        build(false)?;
+        assert_eq!(fs::read_to_string(&recipe1_path)?, "title: recipe 1");
+
+        // clean builds render everything
+        fs::write(&recipe1_path, "unchanged")?;
+        build(true)?;
+        assert_eq!(fs::read_to_string(&recipe1_path)?, "title: recipe 1");
+
+        // outputs of removed recipes are deleted
+        fs::remove_dir_all(recipe_dir.join("recipe 2"))?;
+        build(false)?;
+        assert!(recipe1_path.exists());
+        assert!(!recipe2_path.exists());
+        assert!(!image_path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        fs::create_dir_all(&tera_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ recipe.title }}")?;
+
+        // create invalid recipes
+        for name in ["recipe 1", "recipe 2", "recipe 3"] {
+            fs::create_dir_all(recipe_dir.join(name))?;
+        }
+        fs::write(recipe_dir.join("recipe 1/recipe 1.recipe"), "title")?;
+        fs::write(recipe_dir.join("recipe 2/recipe 2.recipe"), "title")?;
+
+        let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+        let generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+        let result = generator.generate(&recipe_dir, &temp_path.join("output"));
+        assert!(matches!(result, Err(Error::Multiple(errors)) if errors.len() == 3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_names() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ recipe.title }}")?;
+        fs::write(
+            tera_dir.join("redirect.html"),
+            "{{ title }} > {{ path | safe }}",
+        )?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        let mut directory = Directory::from_title(&recipe_dir, "old title")?;
+        directory.store(&recipe)?;
+        recipe.title = "new title".into();
+        directory.update_from_title(&recipe.title)?;
+        recipe.metadata.slug = Some("slug".into());
+        Directory::from_title(&recipe_dir, "other title")?.store(&recipe)?;
+
+        let build = || -> Result<()> {
+            let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+            let generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        build()?;
+
+        // names are derived from directory names or slugs
+        let recipe_output_dir = output_dir.join(RECIPE_DIR);
+        assert!(recipe_output_dir.join("NEW TITLE.html").exists());
+        assert!(recipe_output_dir.join("SLUG.html").exists());
+        let redirect = fs::read_to_string(recipe_output_dir.join("OLD TITLE.html"))?;
+        assert_eq!(redirect, "title > recipes/NEW TITLE.html");
+
+        // colliding names fail the build
+        recipe.metadata.slug = Some("new title".into());
+        Directory::from_title(&recipe_dir, "another title")?.store(&recipe)?;
+        assert!(matches!(build(), Err(Error::OutputNameCollision(..))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tags() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(
+            tera_dir.join("recipe.html"),
+            "{% for tag, path in tag_paths %}{{ tag }}={{ path | safe }};{% endfor %}",
+        )?;
+        fs::write(
+            tera_dir.join("tag.html"),
+            "{{ tag }}:{% for r in recipes %}{{ r.title }},{% endfor %}",
+        )?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe.title = "first".into();
+        recipe.metadata.tags = vec!["a".into(), "b".into()];
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        recipe.title = "second".into();
+        recipe.metadata.tags = vec!["b".into()];
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+
+        let build = || -> Result<()> {
+            let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+            let generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        build()?;
+
+        // a page per tag and links to the pages of the own tags
+        let read = |path: &str| fs::read_to_string(output_dir.join(path));
+        assert_eq!(read("tags/A.html")?, "a:first,");
+        assert_eq!(read("tags/B.html")?, "b:first,second,");
+        assert_eq!(read("recipes/FIRST.html")?, "a=tags/A.html;b=tags/B.html;");
+        assert_eq!(read("recipes/SECOND.html")?, "b=tags/B.html;");
+
+        // tags with the same output name fail the build
+        recipe.title = "third".into();
+        recipe.metadata.tags = vec!["A".into()];
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        assert!(matches!(build(), Err(Error::TagNameCollision(..))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ingredients() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ recipe.title }}")?;
+        fs::write(tera_dir.join("index.html"), "{{ ingredients_path }}")?;
+        fs::write(
+            tera_dir.join("ingredients.html"),
+            "{% for i in ingredients %}{{ i.name }}:\
+            {% for r in i.recipes %}{{ r.title }}={{ r.url | safe }},{% endfor %};{% endfor %}",
+        )?;
+
+        // create recipes
+        for (title, ingredients) in [
+            ("salad", "- Tomatoes\n- oil\n- @[sauce]"),
+            ("sauce", "- tomato\n- Oil\n- tomatoes"),
+        ] {
+            let text = format!(
+                "{title}\n\nYield: 1\n\nIngredients\n{ingredients}\n\nInstructions\n- none"
+            );
+            let recipe = Recipe::parse_from(Cursor::new(text))?;
+            Directory::from_title(&recipe_dir, title)?.store(&recipe)?;
+        }
+
+        let engine = template::Engine::new(&tpl_dir, false, "html", None)?;
+        let mut generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+        generator.synonyms = Synonyms::new([("Tomato".into(), vec!["tomatoes".into()])]);
+        generator.generate(&recipe_dir, &output_dir)?;
+
+        // ingredients grouped by synonyms and case, without references
+        let read = |path: &str| fs::read_to_string(output_dir.join(path));
+        assert_eq!(read("index.html")?, "ingredients.html");
+        assert_eq!(
+            read("ingredients.html")?,
+            "oil:salad=./recipes/SALAD.html,sauce=./recipes/SAUCE.html,;\
+            Tomato:salad=./recipes/SALAD.html,sauce=./recipes/SAUCE.html,;"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_references() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(
+            tera_dir.join("recipe.html"),
+            concat!(
+                "{% for title, url in references %}{{ title }}={{ url | safe }};{% endfor %}",
+                "{% if shopping_list %}{% for i in shopping_list %}",
+                "{{ i.name }}:{{ i.quantity.value.integer }},{% endfor %}{% endif %}",
+                "{% for link in backlinks %}<{{ link.title }}={{ link.url | safe }};{% endfor %}",
+            ),
+        )?;
+
+        // create recipes, references in instructions may be cyclic
+        let store = |text: &str| -> Result<()> {
+            let recipe = Recipe::parse_from(Cursor::new(text))?;
+            let directory = Directory::from_title(&recipe_dir, &recipe.title)?;
+            fs::create_dir_all(directory.path())?;
+            fs::write(directory.recipe_path(), text)?;
+            Ok(())
+        };
+        store(
+            "sauce\n\nYield: 1000 ml\n\nIngredients\n- milk: 1000 ml\n- butter: 60 g\n\nInstructions\n- Serve with @[lasagna]",
+        )?;
+        store(
+            "ragout\n\nYield: 4\n\nIngredients\n- beef: 500 g\n- Butter: 20 g\n\nInstructions\n- none",
+        )?;
+        store(
+            "lasagna\n\nYield: 4\n\nIngredients\n- @[Sauce]: 500 ml\n- @[ragout]: 2\n- pasta: 250 g\n\nInstructions\n- Layer with @[ragout]",
+        )?;
+
+        let build = |expand_references: bool| -> Result<()> {
+            let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+            let mut generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+            generator.expand_references = expand_references;
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        let read = |path: &str| fs::read_to_string(output_dir.join(path));
+
+        // references link to the recipes, which link back
+        build(false)?;
+        assert_eq!(
+            read("recipes/LASAGNA.html")?,
+            "Sauce=../recipes/SAUCE.html;ragout=../recipes/RAGOUT.html;<sauce=../recipes/SAUCE.html;"
+        );
+        assert_eq!(
+            read("recipes/SAUCE.html")?,
+            "lasagna=../recipes/LASAGNA.html;<lasagna=../recipes/LASAGNA.html;"
+        );
+        assert_eq!(
+            read("recipes/RAGOUT.html")?,
+            "<lasagna=../recipes/LASAGNA.html;"
+        );
+
+        // the shopping list contains the scaled and merged ingredients of referenced recipes
+        build(true)?;
+        assert_eq!(
+            read("recipes/LASAGNA.html")?,
+            concat!(
+                "Sauce=../recipes/SAUCE.html;ragout=../recipes/RAGOUT.html;",
+                "milk:500,butter:40,beef:250,pasta:250,<sauce=../recipes/SAUCE.html;"
+            )
+        );
+
+        // cyclic ingredients fail the build
+        store("ragout\n\nYield: 4\n\nIngredients\n- @[lasagna]\n\nInstructions\n- none")?;
+        let Err(Error::Multiple(errors)) = build(true) else {
+            panic!("expected errors of both recipes");
+        };
+        assert_eq!(
+            errors[0].to_string(),
+            "cyclic recipe references: 'lasagna' > 'ragout' > 'lasagna'"
+        );
+        assert!(matches!(errors[1], Error::CyclicRecipeReference(..)));
+
+        // references to unknown recipes fail the build
+        store(
+            "ragout\n\nYield: 4\n\nIngredients\n- beef: 500 g\n\nInstructions\n- See @[béchamel]",
+        )?;
+        assert!(matches!(
+            build(false),
+            Err(Error::DanglingRecipeReference(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_site_url() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ url | safe }} {{ added }}")?;
+        fs::write(tera_dir.join("index.html"), "{{ url | safe }}")?;
+        fs::write(tera_dir.join("tag.html"), "{{ url | safe }}")?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\nTags: tag\nAdded: 2024-03-01\n\nIngredients\n- nothing\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe.title = "first".into();
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        recipe.title = "second".into();
+        recipe.metadata.tags.clear();
+        recipe.metadata.added = None;
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+
+        let build = |site_url: Option<&str>| -> Result<()> {
+            let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+            let mut generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+            generator.site_url = site_url.map(Into::into);
+            generator.feed = Some(FeedOptions::default());
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        let read = |path: &str| fs::read_to_string(output_dir.join(path));
+
+        // without a site URL there are no absolute URLs, sitemap and feed
+        build(None)?;
+        assert_eq!(read("recipes/FIRST.html")?, " 2024-03-01T00:00:00Z");
+        assert!(!output_dir.join(sitemap::SITEMAP_PATH).exists());
+        assert!(!output_dir.join("feed.xml").exists());
+
+        build(Some("https://example.com/"))?;
+        assert_eq!(
+            read("recipes/FIRST.html")?,
+            "https://example.com/recipes/FIRST.html 2024-03-01T00:00:00Z"
+        );
+        assert_eq!(read("index.html")?, "https://example.com/index.html");
+        assert_eq!(read("tags/TAG.html")?, "https://example.com/tags/TAG.html");
+        let sitemap = read(sitemap::SITEMAP_PATH)?;
+        let locs: Vec<_> = sitemap
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("<loc>"))
+            .collect();
+        assert_eq!(
+            locs,
+            [
+                "https://example.com/index.html</loc>",
+                "https://example.com/tags/TAG.html</loc>",
+                "https://example.com/recipes/FIRST.html</loc>",
+                "https://example.com/recipes/SECOND.html</loc>",
+            ]
+        );
+        assert_eq!(sitemap.matches("<lastmod>").count(), 4);
+        assert!(read("feed.xml")?.contains("<published>2024-03-01T00:00:00Z</published>"));
+
+        // the sitemap and the feed are removed again with the site URL
+        build(None)?;
+        assert!(!output_dir.join(sitemap::SITEMAP_PATH).exists());
+        assert!(!output_dir.join("feed.xml").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pages() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let pages_dir = tera_dir.join(template::PAGES_DIR);
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(pages_dir.join("data"))?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(tera_dir.join("recipe.html"), "{{ recipe.title }}")?;
+        fs::write(
+            tera_dir.join("base.html"),
+            "{% block main %}{% endblock main %}",
+        )?;
+        fs::write(
+            pages_dir.join("about.html"),
+            "{% extends \"base.html\" %}{% block main %}{{ \"<\" }}{{ recipes | length }}{% endblock main %}",
+        )?;
+        fs::write(
+            pages_dir.join("feed.xml"),
+            "{% for r in recipes %}<title>{{ r.title }}{{ \"&\" }}</title>{% endfor %}",
+        )?;
+        fs::write(
+            pages_dir.join("stats.txt"),
+            concat!(
+                "{{ stats.recipe_count }} {{ stats.ingredient_count }} {{ stats.tag_counts.x }} ",
+                "{% for i in stats.common_ingredients %}{{ i.name }}={{ i.count }} {% endfor %}",
+                "{% for r in recipes | sort_recipes(reverse=true) %}{{ r.recipe.title }}{% endfor %}",
+            ),
+        )?;
+        fs::write(
+            pages_dir.join("data/tags.json"),
+            "{{ base_url }}:{{ tags | join(sep=\"/\") }}",
+        )?;
+
+        // create recipes
+        let recipe_str = "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- none";
+        let mut recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        recipe.title = "b".into();
+        recipe.metadata.tags = vec!["x".into(), "y".into()];
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+        recipe.title = "a".into();
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+
+        let engine = template::Engine::new(&tpl_dir, true, "html", None)?;
+        let mut generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+        generator.layout.search_index_path = Some("data/search.json".into());
+        generator.generate(&recipe_dir, &output_dir)?;
+
+        // pages are rendered to their path within the pages directory, only
+        // HTML and XML pages are escaped
+        let read = |path: &str| fs::read_to_string(output_dir.join(path));
+        assert_eq!(read("about.html")?, "&lt;2");
+        assert_eq!(
+            read("feed.xml")?,
+            "<title>a&amp;</title><title>b&amp;</title>"
+        );
+        assert_eq!(read("data/tags.json")?, "..:x/y");
+        assert_eq!(read("stats.txt")?, "2 2 2 nothing=2 ba");
+
+        // the search index is written next to the pages
+        let search_index: Value = serde_json::from_str(&read("data/search.json")?).unwrap();
+        assert_eq!(search_index["recipes"][0]["path"], "recipes/A.html");
+        assert_eq!(
+            search_index["terms"]["nothing"],
+            serde_json::json!([[0, 2], [1, 2]])
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_gallery() -> Result<()> {
+        // create temp directories
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = temp_path.join("template");
+        let tera_dir = tpl_dir.join("tera");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&tera_dir)?;
+        fs::create_dir_all(&recipe_dir)?;
+        fs::write(
+            tera_dir.join("recipe.html"),
+            concat!(
+                "{% for i in recipe.instructions.items %}{{ i | strip_image_refs }}",
+                "{% for g in i | image_refs(gallery=gallery) %}[{{ g.path }}]{% endfor %}{% endfor %}|",
+                "{% for g in gallery %}{{ g.name }}:{{ g.caption | default(value=\"-\") }} {% endfor %}",
+            ),
+        )?;
+
+        // create recipe with gallery
+        let recipe_str =
+            "title\n\nYield: 1\n\nIngredients\n- nothing\n\nInstructions\n- mix @image(2)";
+        let recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        let mut directory = Directory::from_title(&recipe_dir, &recipe.title)?;
+        directory.store(&recipe)?;
+        let gallery_path = directory.gallery_path();
+        fs::create_dir_all(&gallery_path)?;
+        fs::write(gallery_path.join("a.svg"), "a")?;
+        fs::write(gallery_path.join("b.svg"), "b")?;
+        fs::write(gallery_path.join("notes.txt"), "ignored")?;
+        fs::write(
+            gallery_path.join(crate::recipe::directory::CAPTIONS_FILE_NAME),
+            "\"a.svg\" = \"Dough\"",
+        )?;
+
+        let engine = template::Engine::new(&tpl_dir, false, "html", None)?;
+        let generator = Generator::new(
+            engine,
+            vec![OsString::from("svg")],
+            "html".into(),
+            FileNameFilter,
+        );
+        generator.generate(&recipe_dir, &output_dir)?;
+
+        let gallery_dir = output_dir.join(IMAGE_DIR).join("TITLE");
+        assert_eq!(fs::read_to_string(gallery_dir.join("B.svg"))?, "b");
+        assert_eq!(
+            fs::read_to_string(output_dir.join("recipes/TITLE.html"))?,
+            "mix[images/TITLE/B.svg]|a.svg:Dough b.svg:- "
+        );
+
+        // removed gallery images are removed from the output
+        directory.remove_gallery_image("b.svg".as_ref())?;
+        let recipe_str = recipe_str.replace("@image(2)", "@image(a.svg)");
+        fs::write(directory.recipe_path(), recipe_str)?;
+        generator.generate(&recipe_dir, &output_dir)?;
+        assert!(!gallery_dir.join("B.svg").exists());
+        assert_eq!(
+            fs::read_to_string(output_dir.join("recipes/TITLE.html"))?,
+            "mix[images/TITLE/A.svg]|a.svg:Dough "
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bundled_html_cost() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let temp_path = temp_dir.path();
+        let tpl_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/templates/html");
+        let recipe_dir = temp_path.join(RECIPE_DIR);
+        let output_dir = temp_path.join("output");
+        fs::create_dir_all(&recipe_dir)?;
+        let recipe_str =
+            "Bread\n\nYield: 2\n\nIngredients\n- flour: 500 g\n- salt\n\nInstructions\n- Bake";
+        let recipe = Recipe::parse_from(Cursor::new(recipe_str))?;
+        Directory::from_title(&recipe_dir, &recipe.title)?.store(&recipe)?;
+
+        let build = |prices: Option<Prices>| -> Result<()> {
+            let mut context = Context::new();
+            context.insert("lf", "\n");
+            context.insert(
+                "app",
+                &HashMap::from([("name", "name"), ("homepage", "homepage")]),
+            );
+            let mut engine = template::Engine::new(&tpl_dir, true, "html", Some("en"))?;
+            engine.forced_context = Some(context);
+            let mut generator = Generator::new(engine, Vec::new(), "html".into(), FileNameFilter);
+            generator.prices = prices;
+            generator.generate(&recipe_dir, &output_dir)
+        };
+        let read = || fs::read_to_string(output_dir.join(RECIPE_DIR).join("BREAD.html"));
+
+        // without a price list there is no cost
+        build(None)?;
+        assert!(!read()?.contains("entry cost"));
+
+        // with a price list the cost per serving is shown
+        fs::write(
+            temp_path.join(PRICES_FILE_NAME),
+            "currency = \"EUR\"\n[prices]\nflour = { price = 1.2, per = \"1 kg\" }\n",
+        )?;
+        build(Prices::load(temp_path, &Synonyms::default())?)?;
+        let page = read()?;
+        assert!(page.contains("<span class=\"value\">≥ 0.30 EUR</span>"));
+        assert!(page.contains("title=\"excluding: salt\""));
+
+        Ok(())
+    }
+}
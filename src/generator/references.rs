@@ -0,0 +1,261 @@
+//! Resolution of references between recipes (`@[Title]`) and their
+//! expansion into shopping lists.
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    path::Path,
+};
+
+use crate::{
+    error::{self, Error, Result},
+    recipe::{
+        Recipe,
+        directory::Directory,
+        ingredient::{Ingredient, reduce},
+        redirects::Redirects,
+        reference::find_references,
+        synonyms::{Synonyms, normalize},
+    },
+};
+
+use super::{Source, manifest::Hasher};
+
+/// Resolves the references of all `sources` to the directory names of the
+/// referenced recipes, following renamed recipes and ignoring case if there
+/// is no exact match. References to unknown recipes are errors.
+///
+/// The hash of each source includes the hashes of all recipes it references
+/// (directly or indirectly). With `expand`, recipes with ingredients
+/// referencing other recipes get a shopping list, in which the same
+/// ingredients (by their `synonyms`) are merged.
+pub(super) fn resolve(
+    recipe_dir: &Path,
+    sources: &mut [Source],
+    expand: bool,
+    synonyms: &Synonyms,
+) -> Result<()> {
+    let redirects = Redirects::load(recipe_dir)?;
+    let indices: HashMap<OsString, usize> = sources
+        .iter()
+        .enumerate()
+        .map(|(index, source)| (source.directory.base_name().into(), index))
+        .collect();
+    let lowercase_names: HashMap<_, _> = indices
+        .keys()
+        .map(|name| (name.to_string_lossy().to_lowercase(), name))
+        .collect();
+    let find = |title: &str| -> Result<Option<OsString>> {
+        let directory = Directory::from_title(recipe_dir, title)?;
+        let name = directory.base_name().to_string_lossy();
+        let name = if indices.contains_key(directory.base_name()) {
+            Some(directory.base_name())
+        } else if let Some(name) = redirects.resolve(&name) {
+            indices
+                .get_key_value(OsStr::new(name))
+                .map(|(name, _)| name.as_os_str())
+        } else {
+            lowercase_names
+                .get(&name.to_lowercase())
+                .map(|name| name.as_os_str())
+        };
+        Ok(name.map(Into::into))
+    };
+    let results = sources.iter().map(|source| {
+        let mut references = BTreeMap::new();
+        let mut errors = Vec::new();
+        for title in source.recipe.references() {
+            match find(title)? {
+                Some(name) => {
+                    references.insert(title.to_string(), name);
+                }
+                None => errors.push(Err(Error::DanglingRecipeReference(
+                    source.recipe.title.clone(),
+                    title.into(),
+                ))),
+            }
+        }
+        error::collect::<(), _>(errors)?;
+        Ok(references)
+    });
+    let references = error::collect(results.collect::<Vec<_>>())?;
+    let graph = Graph {
+        sources,
+        indices: &indices,
+        references: &references,
+        synonyms,
+    };
+    let hashes: Vec<_> = (0..sources.len()).map(|index| graph.hash(index)).collect();
+    let shopping_lists = if expand {
+        let lists = (0..sources.len()).map(|index| graph.shopping_list(index));
+        error::collect(lists.collect::<Vec<_>>())?
+    } else {
+        vec![None; sources.len()]
+    };
+    let values = references.into_iter().zip(hashes).zip(shopping_lists);
+    for (source, ((references, hash), shopping_list)) in sources.iter_mut().zip(values) {
+        source.references = references;
+        source.hash = hash;
+        source.shopping_list = shopping_list;
+    }
+    Ok(())
+}
+
+struct Graph<'a> {
+    sources: &'a [Source],
+    indices: &'a HashMap<OsString, usize>,
+    references: &'a [BTreeMap<String, OsString>],
+    synonyms: &'a Synonyms,
+}
+
+impl Graph<'_> {
+    fn referenced(&self, index: usize, title: &str) -> Option<usize> {
+        let name = self.references[index].get(title)?;
+        self.indices.get(name).copied()
+    }
+
+    /// Hash of the source and all recipes it references.
+    fn hash(&self, index: usize) -> String {
+        let mut visited = HashSet::from([index]);
+        let mut stack = vec![index];
+        while let Some(index) = stack.pop() {
+            for name in self.references[index].values() {
+                let referenced = self.indices[name];
+                if visited.insert(referenced) {
+                    stack.push(referenced);
+                }
+            }
+        }
+        if visited.len() == 1 {
+            return self.sources[index].hash.clone();
+        }
+        let mut visited: Vec<_> = visited.into_iter().collect();
+        visited.sort_unstable();
+        let mut hasher = Hasher::default();
+        for index in visited {
+            hasher.update(&self.sources[index].hash);
+            hasher.update(self.sources[index].name.as_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Returns the ingredients of the recipe with the (scaled) ingredients of
+    /// referenced recipes instead of the referencing ingredients, if any.
+    fn shopping_list(&self, index: usize) -> Result<Option<Vec<Ingredient>>> {
+        let recipe = &self.sources[index].recipe;
+        if !recipe
+            .ingredients
+            .iter()
+            .any(|ingredient| sub_recipe_title(ingredient).is_some())
+        {
+            return Ok(None);
+        }
+        let mut list = Vec::new();
+        self.expand(index, (1, 1), &mut vec![index], &mut list)?;
+        Ok(Some(merge(list, self.synonyms)))
+    }
+
+    fn expand(
+        &self,
+        index: usize,
+        (numer, denom): (u64, u64),
+        path: &mut Vec<usize>,
+        list: &mut Vec<Ingredient>,
+    ) -> Result<()> {
+        for ingredient in self.sources[index].recipe.ingredients.iter() {
+            let referenced =
+                sub_recipe_title(ingredient).and_then(|title| self.referenced(index, title));
+            let Some(referenced) = referenced else {
+                let mut ingredient = ingredient.clone();
+                if let Some(quantity) = &mut ingredient.quantity
+                    && numer != denom
+                {
+                    quantity.value = quantity.value.scale(numer, denom);
+                }
+                list.push(ingredient);
+                continue;
+            };
+            if path.contains(&referenced) {
+                let titles: Vec<_> = path
+                    .iter()
+                    .chain([&referenced])
+                    .map(|&index| self.sources[index].recipe.title.as_str())
+                    .collect();
+                return Err(Error::CyclicRecipeReference(titles.join("' > '")));
+            }
+            let (sub_numer, sub_denom) = factor(ingredient, &self.sources[referenced].recipe);
+            let factor = multiply((numer, denom), (sub_numer, sub_denom));
+            path.push(referenced);
+            self.expand(referenced, factor, path, list)?;
+            path.pop();
+        }
+        Ok(())
+    }
+}
+
+/// Merges ingredients of the same identifier (see [`Synonyms::id`]), kind
+/// and unit by adding up their quantities. The first one keeps its place,
+/// name and note.
+fn merge(list: Vec<Ingredient>, synonyms: &Synonyms) -> Vec<Ingredient> {
+    let mut merged: Vec<Ingredient> = Vec::new();
+    let mut indices: HashMap<_, usize> = HashMap::new();
+    for ingredient in list {
+        let key = (
+            synonyms.id(&ingredient.name),
+            ingredient.kind.as_deref().map(normalize),
+            // ingredients without quantity are only merged with each other
+            ingredient
+                .quantity
+                .as_ref()
+                .map(|quantity| quantity.unit.as_deref().map(normalize)),
+        );
+        match indices.get(&key) {
+            Some(&index) => {
+                let quantities = (&mut merged[index].quantity, &ingredient.quantity);
+                if let (Some(first), Some(other)) = quantities {
+                    first.value = first.value.sum(&other.value);
+                }
+            }
+            None => {
+                indices.insert(key, merged.len());
+                merged.push(ingredient);
+            }
+        }
+    }
+    merged
+}
+
+/// Returns the title of a referenced recipe if the ingredient name is only
+/// a reference.
+fn sub_recipe_title(ingredient: &Ingredient) -> Option<&str> {
+    let name = &ingredient.name;
+    let mut references = find_references(name);
+    match (references.next(), references.next()) {
+        (Some((range, title)), None) if range == (0..name.len()) => Some(title),
+        _ => None,
+    }
+}
+
+/// Returns the share of `recipe` used by an ingredient: its quantity in the
+/// unit of the recipe yield relative to the yield, otherwise the whole recipe.
+fn factor(ingredient: &Ingredient, recipe: &Recipe) -> (u64, u64) {
+    let yields = &recipe.metadata.yields;
+    match &ingredient.quantity {
+        Some(quantity) if quantity.unit == yields.unit && yields.value > 0 => {
+            let (numer, denom) = quantity.value.ratio();
+            multiply((numer, denom), (1, yields.value.into()))
+        }
+        _ => (1, 1),
+    }
+}
+
+fn multiply((a_numer, a_denom): (u64, u64), (b_numer, b_denom): (u64, u64)) -> (u64, u64) {
+    let (numer, denom) = reduce(
+        u128::from(a_numer) * u128::from(b_numer),
+        u128::from(a_denom) * u128::from(b_denom),
+    );
+    (
+        numer.try_into().unwrap_or(u64::MAX),
+        denom.try_into().unwrap_or(u64::MAX),
+    )
+}
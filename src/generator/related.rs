@@ -0,0 +1,114 @@
+//! Related recipes by shared tags and ingredients.
+
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use crate::recipe::{Recipe, synonyms::Synonyms};
+
+/// Weight of a shared tag relative to a shared ingredient equally common.
+const TAG_WEIGHT: f64 = 2.0;
+
+#[derive(PartialEq, Eq, Hash)]
+enum Feature<'a> {
+    Tag(&'a str),
+    /// Ingredient identifier, see [`Synonyms::id`]
+    Ingredient(String),
+}
+
+/// Returns the indices of up to `count` related recipes for each of
+/// `recipes`, the most similar first.
+///
+/// The similarity of two recipes is the sum of the weights of their shared
+/// tags and ingredients (matched by their `synonyms`). Weights decrease with the number
+/// of recipes using a tag or an ingredient, so that a rare ingredient counts
+/// more than salt. Ties keep the order of `recipes`.
+pub fn related(recipes: &[&Recipe], count: usize, synonyms: &Synonyms) -> Vec<Vec<usize>> {
+    let mut users: HashMap<Feature, Vec<usize>> = HashMap::new();
+    for (index, recipe) in recipes.iter().enumerate() {
+        let tags = recipe.metadata.tags.iter().map(|tag| Feature::Tag(tag));
+        let ingredients = recipe
+            .ingredients
+            .iter()
+            .map(|ingredient| Feature::Ingredient(synonyms.id(&ingredient.name)));
+        let features: HashSet<_> = tags.chain(ingredients).collect();
+        for feature in features {
+            users.entry(feature).or_default().push(index);
+        }
+    }
+    let mut scores = vec![HashMap::new(); recipes.len()];
+    for (feature, users) in &users {
+        if users.len() < 2 {
+            continue;
+        }
+        // smoothed inverse document frequency
+        let mut weight = (1.0 + recipes.len() as f64 / users.len() as f64).ln();
+        if let Feature::Tag(_) = feature {
+            weight *= TAG_WEIGHT;
+        }
+        for &a in users {
+            for &b in users.iter().filter(|&&b| b != a) {
+                *scores[a].entry(b).or_insert(0.0) += weight;
+            }
+        }
+    }
+    scores
+        .into_iter()
+        .map(|scores| {
+            let mut related: Vec<(usize, f64)> = scores.into_iter().collect();
+            related.sort_by(|(a, a_score), (b, b_score)| {
+                b_score
+                    .partial_cmp(a_score)
+                    .unwrap_or(Ordering::Equal)
+                    .then(a.cmp(b))
+            });
+            related
+                .into_iter()
+                .take(count)
+                .map(|(index, _)| index)
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn recipe(tags: &str, ingredients: &[&str]) -> Recipe {
+        let ingredients: String = ingredients
+            .iter()
+            .map(|name| format!("- {name}\n"))
+            .collect();
+        let text = format!(
+            "title\n\nYield: 1\nTags: {tags}\n\nIngredients\n{ingredients}\nInstructions\n- none"
+        );
+        Recipe::parse_from(Cursor::new(text)).unwrap()
+    }
+
+    #[test]
+    fn test_related() {
+        let recipes = [
+            recipe("pasta", &["Spaghetti", "tomatoes", "salt"]),
+            recipe("soup", &["tomatoes", "salt"]),
+            recipe("pasta", &["spaghetti", "salt"]),
+            recipe("cake", &["flour", "salt"]),
+            recipe("drink", &["water"]),
+        ];
+        let recipes: Vec<_> = recipes.iter().collect();
+        assert_eq!(
+            related(&recipes, 3, &Synonyms::default()),
+            [
+                vec![2, 1, 3],
+                vec![0, 2, 3],
+                vec![0, 1, 3],
+                vec![0, 1, 2],
+                vec![]
+            ]
+        );
+        assert_eq!(related(&recipes, 1, &Synonyms::default())[0], [2]);
+    }
+}
@@ -0,0 +1,201 @@
+//! Search index of recipe titles, tags, ingredient names and instructions
+//! for client-side search.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::Path,
+};
+
+use serde::Serialize;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use crate::recipe::{Recipe, image_ref::remove_image_refs, synonyms::Synonyms};
+
+// Fields in which a term occurs, combined as bit flags. The flags are
+// ordered by relevance, so that a higher value means a better match.
+pub const FIELD_TITLE: u8 = 8;
+pub const FIELD_TAG: u8 = 4;
+pub const FIELD_INGREDIENT: u8 = 2;
+pub const FIELD_TEXT: u8 = 1;
+
+const ENGLISH_STOPWORDS: &[&str] = &[
+    "a", "about", "after", "all", "an", "and", "any", "are", "as", "at", "be", "before", "but",
+    "by", "can", "each", "for", "from", "if", "in", "into", "is", "it", "its", "of", "on", "or",
+    "out", "over", "the", "then", "there", "this", "to", "until", "up", "with",
+];
+
+const GERMAN_STOPWORDS: &[&str] = &[
+    "ab", "alle", "als", "am", "an", "auf", "aus", "bei", "bis", "das", "dann", "dem", "den",
+    "der", "des", "die", "ein", "eine", "einem", "einen", "einer", "es", "fur", "im", "in", "ist",
+    "mit", "nach", "oder", "sich", "so", "und", "unter", "uber", "vom", "von", "vor", "wenn", "zu",
+    "zum", "zur",
+];
+
+/// Splits text into normalized search terms.
+///
+/// Terms are lowercase and without diacritics (`ß` becomes `ss`), so that
+/// the client only needs to apply `String.normalize("NFD")`, strip
+/// combining marks and lowercase to match them. Stopwords of the language
+/// and single characters are skipped.
+pub struct Tokenizer {
+    stopwords: HashSet<&'static str>,
+}
+
+impl Tokenizer {
+    pub fn new(language: Option<&str>) -> Self {
+        Self {
+            stopwords: stopwords(language).iter().copied().collect(),
+        }
+    }
+
+    pub fn stopwords(&self) -> Vec<&'static str> {
+        let mut stopwords: Vec<_> = self.stopwords.iter().copied().collect();
+        stopwords.sort_unstable();
+        stopwords
+    }
+
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let text: String = text
+            .nfd()
+            .filter(|&c| !is_combining_mark(c))
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+            .replace('ß', "ss");
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|term| term.chars().nth(1).is_some() && !self.stopwords.contains(term))
+            .map(Into::into)
+            .collect()
+    }
+}
+
+fn stopwords(language: Option<&str>) -> &'static [&'static str] {
+    match language {
+        Some("de") => GERMAN_STOPWORDS,
+        Some("en") | None => ENGLISH_STOPWORDS,
+        Some(_) => &[],
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Document {
+    title: String,
+    path: String,
+}
+
+/// An inverted index mapping every term to the recipes (by index) and the
+/// fields it occurs in.
+///
+/// Serialized as JSON, it is compact enough to be loaded by a static site.
+#[derive(Debug, Serialize)]
+pub struct SearchIndex {
+    stopwords: Vec<&'static str>,
+    recipes: Vec<Document>,
+    terms: BTreeMap<String, Vec<(usize, u8)>>,
+}
+
+impl SearchIndex {
+    /// Builds the index of `recipes` with their output paths.
+    ///
+    /// Ingredients are also found by their canonical name and its variants
+    /// in `synonyms`.
+    pub fn new<'a, I>(recipes: I, language: Option<&str>, synonyms: &Synonyms) -> Self
+    where
+        I: IntoIterator<Item = (&'a Recipe, &'a Path)>,
+    {
+        let tokenizer = Tokenizer::new(language);
+        let mut index = Self {
+            stopwords: tokenizer.stopwords(),
+            recipes: Vec::new(),
+            terms: BTreeMap::new(),
+        };
+        for (recipe, path) in recipes {
+            let mut fields: BTreeMap<String, u8> = BTreeMap::new();
+            let mut add = |text: &str, field| {
+                for term in tokenizer.tokenize(text) {
+                    *fields.entry(term).or_default() |= field;
+                }
+            };
+            add(&recipe.title, FIELD_TITLE);
+            for tag in &recipe.metadata.tags {
+                add(tag, FIELD_TAG);
+            }
+            for ingredient in recipe.ingredients.iter() {
+                add(&ingredient.name, FIELD_INGREDIENT);
+                for variant in synonyms.variants(&ingredient.name) {
+                    add(variant, FIELD_INGREDIENT);
+                }
+            }
+            let texts = recipe.description.iter().chain(recipe.instructions.iter());
+            for text in texts.chain(&recipe.notes) {
+                add(&remove_image_refs(text), FIELD_TEXT);
+            }
+            let document = index.recipes.len();
+            for (term, fields) in fields {
+                index
+                    .terms
+                    .entry(term)
+                    .or_default()
+                    .push((document, fields));
+            }
+            index.recipes.push(Document {
+                title: recipe.title.clone(),
+                path: path.to_string_lossy().replace('\\', "/"),
+            });
+        }
+        index
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::error::Result;
+
+    #[test]
+    fn test_tokenize() {
+        let tokenizer = Tokenizer::new(Some("en"));
+        assert_eq!(
+            tokenizer.tokenize("Crème Brûlée (with a Twist) in 3-4 minutes"),
+            ["creme", "brulee", "twist", "minutes"]
+        );
+        let tokenizer = Tokenizer::new(Some("de"));
+        assert_eq!(
+            tokenizer.tokenize("Süßkartoffeln für den Ofen"),
+            ["susskartoffeln", "ofen"]
+        );
+        let tokenizer = Tokenizer::new(Some("xx"));
+        assert_eq!(tokenizer.tokenize("The Борщ"), ["the", "борщ"]);
+    }
+
+    #[test]
+    fn test_search_index() -> Result<()> {
+        let recipe = Recipe::parse_from(Cursor::new(
+            "Tomato Soup\n\nYield: 2\nTags: Soup, Vegan\n\nIngredients\n- 500 g tomatoes\n- salt\n\nInstructions\n- Simmer the tomatoes. @image(1)\n- Add salt to taste",
+        ))?;
+        let synonyms = Synonyms::new([("salt".to_string(), vec!["Salz".to_string()])]);
+        let index = SearchIndex::new(
+            [(&recipe, Path::new("recipes/tomato-soup.html"))],
+            None,
+            &synonyms,
+        );
+        assert_eq!(index.recipes[0].path, "recipes/tomato-soup.html");
+        assert_eq!(index.terms["soup"], [(0, FIELD_TITLE | FIELD_TAG)]);
+        assert_eq!(
+            index.terms["tomatoes"],
+            [(0, FIELD_INGREDIENT | FIELD_TEXT)]
+        );
+        assert_eq!(index.terms["salt"], [(0, FIELD_INGREDIENT | FIELD_TEXT)]);
+        assert_eq!(index.terms["salz"], [(0, FIELD_INGREDIENT)]);
+        assert!(!index.terms.contains_key("image"));
+        assert!(!index.terms.contains_key("the"));
+        let json = index.to_json();
+        assert!(json.contains(r#""terms":{"#));
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! Sitemap of the index, tag and recipe pages for search engines.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use crate::util::escape_xml;
+
+use super::{IndexEntry, layout::absolute_url};
+
+pub const SITEMAP_PATH: &str = "sitemap.xml";
+
+/// Renders the sitemap of the index page (at `index_path`), the tag pages
+/// and all recipes. Pages listing recipes were last modified with their
+/// latest recipe.
+pub(super) fn render(
+    entries: &[IndexEntry],
+    index_path: Option<&Path>,
+    tag_paths: &BTreeMap<String, PathBuf>,
+    site_url: &str,
+) -> String {
+    let mut urls: Vec<(&Path, Option<&str>)> = Vec::new();
+    if let Some(path) = index_path {
+        urls.push((path, last_updated(entries.iter())));
+    }
+    for (tag, path) in tag_paths {
+        let tagged = entries.iter().filter(|entry| entry.tags.contains(tag));
+        urls.push((path, last_updated(tagged)));
+    }
+    for entry in entries {
+        urls.push((&entry.path, Some(&entry.dates.updated)));
+    }
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for (path, last_modified) in urls {
+        xml.push_str("  <url>\n");
+        let _ = writeln!(
+            xml,
+            "    <loc>{}</loc>",
+            escape_xml(&absolute_url(site_url, path))
+        );
+        if let Some(last_modified) = last_modified {
+            let _ = writeln!(xml, "    <lastmod>{last_modified}</lastmod>");
+        }
+        xml.push_str("  </url>\n");
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+fn last_updated<'a>(entries: impl Iterator<Item = &'a IndexEntry>) -> Option<&'a str> {
+    entries.map(|entry| entry.dates.updated.as_str()).max()
+}
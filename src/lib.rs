@@ -2,6 +2,7 @@
 
 pub mod error;
 pub mod generator;
+pub mod pdf;
 pub mod recipe;
 pub mod template;
 pub mod util;
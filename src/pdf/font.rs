@@ -0,0 +1,211 @@
+//! The bundled Merriweather fonts, each style split into Unicode subsets.
+
+use std::collections::HashMap;
+
+use crate::{
+    TEMPLATE_DIR,
+    error::{Error, Result},
+};
+
+use super::woff2;
+
+const FONT_DIR: &str = "html/static/fonts/merriweather";
+const SUBSETS: [&str; 5] = [
+    "latin",
+    "latin-ext",
+    "cyrillic",
+    "cyrillic-ext",
+    "vietnamese",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Style {
+    Regular,
+    Bold,
+}
+
+impl Style {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Regular => "Regular",
+            Self::Bold => "Bold",
+        }
+    }
+
+    fn weight(self) -> u16 {
+        match self {
+            Self::Regular => 400,
+            Self::Bold => 700,
+        }
+    }
+}
+
+/// A TrueType font. All metrics are in glyph space units (1/1000 em).
+pub(crate) struct Face {
+    pub name: String,
+    pub data: Vec<u8>,
+    pub ascender: f32,
+    pub descender: f32,
+    pub cap_height: f32,
+    pub bbox: [f32; 4],
+    advances: Vec<f32>,
+    glyphs: HashMap<char, u16>,
+}
+
+impl Face {
+    fn new(name: String, data: Vec<u8>) -> Result<Self> {
+        let face = ttf_parser::Face::parse(&data, 0).map_err(|_| Error::InvalidFont("parse"))?;
+        let scale = 1000.0 / f32::from(face.units_per_em());
+        let advances = (0..face.number_of_glyphs())
+            .map(|id| {
+                let advance = face.glyph_hor_advance(ttf_parser::GlyphId(id));
+                f32::from(advance.unwrap_or_default()) * scale
+            })
+            .collect();
+        let mut glyphs = HashMap::new();
+        if let Some(cmap) = face.tables().cmap {
+            for subtable in cmap
+                .subtables
+                .into_iter()
+                .filter(|table| table.is_unicode())
+            {
+                subtable.codepoints(|codepoint| {
+                    let glyph = char::from_u32(codepoint)
+                        .zip(subtable.glyph_index(codepoint))
+                        .filter(|(_, glyph)| glyph.0 != 0);
+                    if let Some((char, glyph)) = glyph {
+                        glyphs.entry(char).or_insert(glyph.0);
+                    }
+                });
+            }
+        }
+        let bbox = face.global_bounding_box();
+        let cap_height = face.capital_height().unwrap_or(face.ascender());
+        Ok(Self {
+            name,
+            ascender: f32::from(face.ascender()) * scale,
+            descender: f32::from(face.descender()) * scale,
+            cap_height: f32::from(cap_height) * scale,
+            bbox: [bbox.x_min, bbox.y_min, bbox.x_max, bbox.y_max].map(|v| f32::from(v) * scale),
+            advances,
+            glyphs,
+            data,
+        })
+    }
+
+    pub fn advance(&self, glyph: u16) -> f32 {
+        self.advances
+            .get(usize::from(glyph))
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Consecutive characters of a text that use the same face.
+#[derive(Clone, Debug)]
+pub(crate) struct Run {
+    /// Index of the face in [`Fonts::faces`]
+    pub face: usize,
+    /// Glyph ids with the characters they represent
+    pub glyphs: Vec<(u16, char)>,
+    /// Width in glyph space units
+    pub width: f32,
+}
+
+/// All faces of the regular and the bold style.
+pub(crate) struct Fonts {
+    faces: Vec<Face>,
+    regular: Vec<usize>,
+    bold: Vec<usize>,
+}
+
+impl Fonts {
+    pub fn load() -> Result<Self> {
+        let mut fonts = Self {
+            faces: Vec::new(),
+            regular: Vec::new(),
+            bold: Vec::new(),
+        };
+        for style in [Style::Regular, Style::Bold] {
+            for subset in SUBSETS {
+                let path = format!("{FONT_DIR}/{}/{subset}.woff2", style.weight());
+                let file = TEMPLATE_DIR
+                    .get_file(&path)
+                    .ok_or(Error::InvalidFont("missing font file"))?;
+                let name = format!("Merriweather-{}-{subset}", style.name());
+                fonts
+                    .faces
+                    .push(Face::new(name, woff2::decode(file.contents())?)?);
+                let index = fonts.faces.len() - 1;
+                match style {
+                    Style::Regular => fonts.regular.push(index),
+                    Style::Bold => fonts.bold.push(index),
+                }
+            }
+        }
+        Ok(fonts)
+    }
+
+    pub fn faces(&self) -> &[Face] {
+        &self.faces
+    }
+
+    /// Splits a text into runs, using the first face of the style that
+    /// supports a character. Unsupported characters are replaced by the
+    /// `.notdef` glyph of the first face.
+    pub fn shape(&self, text: &str, style: Style) -> Vec<Run> {
+        let faces = match style {
+            Style::Regular => &self.regular,
+            Style::Bold => &self.bold,
+        };
+        let mut runs: Vec<Run> = Vec::new();
+        for char in text.chars() {
+            let (face, glyph) = faces
+                .iter()
+                .find_map(|&face| Some((face, *self.faces[face].glyphs.get(&char)?)))
+                .unwrap_or((faces[0], 0));
+            let advance = self.faces[face].advance(glyph);
+            match runs.last_mut() {
+                Some(run) if run.face == face => {
+                    run.glyphs.push((glyph, char));
+                    run.width += advance;
+                }
+                _ => runs.push(Run {
+                    face,
+                    glyphs: vec![(glyph, char)],
+                    width: advance,
+                }),
+            }
+        }
+        runs
+    }
+
+    /// Returns the width of a text in glyph space units.
+    pub fn width(&self, text: &str, style: Style) -> f32 {
+        self.shape(text, style).iter().map(|run| run.width).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape() -> Result<()> {
+        let fonts = Fonts::load()?;
+        let runs = fonts.shape("Borscht (Борщ)", Style::Bold);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(
+            fonts.faces()[runs[1].face].name,
+            "Merriweather-Bold-cyrillic"
+        );
+        assert!(
+            runs.iter()
+                .flat_map(|run| &run.glyphs)
+                .all(|&(glyph, _)| glyph != 0)
+        );
+        assert!(fonts.width("mm", Style::Regular) > fonts.width("ii", Style::Regular));
+        assert!(fonts.width("m", Style::Bold) > fonts.width("m", Style::Regular));
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Placement of text, images and links on pages, including line breaking
+//! and page breaks.
+
+use std::mem;
+
+use super::font::{Fonts, Run, Style};
+
+pub(crate) const MARGIN: f32 = 56.0;
+const LINE_HEIGHT: f32 = 1.4;
+// distance from the top of a line to its baseline (relative to the font size)
+const BASELINE: f32 = 1.1;
+
+pub(crate) enum Op {
+    Text {
+        x: f32,
+        y: f32,
+        size: f32,
+        gray: f32,
+        run: Run,
+    },
+    Image {
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+        /// Index of the image in the document
+        index: usize,
+    },
+    /// Horizontal line
+    Rule { x: f32, y: f32, width: f32 },
+    /// Link to another page of the document
+    Link { rect: [f32; 4], page: usize },
+}
+
+#[derive(Default)]
+pub(crate) struct Page {
+    pub ops: Vec<Op>,
+}
+
+/// Text with a single style.
+pub(crate) struct Span<'a> {
+    pub text: &'a str,
+    pub style: Style,
+}
+
+impl<'a> Span<'a> {
+    pub fn regular(text: &'a str) -> Self {
+        Self {
+            text,
+            style: Style::Regular,
+        }
+    }
+
+    pub fn bold(text: &'a str) -> Self {
+        Self {
+            text,
+            style: Style::Bold,
+        }
+    }
+}
+
+/// A line of wrapped text with the horizontal offsets of its runs.
+#[derive(Default)]
+pub(crate) struct Line {
+    runs: Vec<(f32, Run)>,
+    pub width: f32,
+}
+
+/// Lays out content from top to bottom, starting a new page whenever the
+/// current one is full.
+pub(crate) struct Composer<'a> {
+    fonts: &'a Fonts,
+    width: f32,
+    height: f32,
+    pages: Vec<Page>,
+    /// Top of the next content
+    y: f32,
+}
+
+impl<'a> Composer<'a> {
+    pub fn new(fonts: &'a Fonts, (width, height): (f32, f32)) -> Self {
+        Self {
+            fonts,
+            width,
+            height,
+            pages: vec![Page::default()],
+            y: height - MARGIN,
+        }
+    }
+
+    pub fn into_pages(self) -> Vec<Page> {
+        self.pages
+    }
+
+    /// Index of the current page
+    pub fn page(&self) -> usize {
+        self.pages.len() - 1
+    }
+
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+
+    pub fn content_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN
+    }
+
+    fn top(&self) -> f32 {
+        self.height - MARGIN
+    }
+
+    /// Starts a new page unless the current one is still empty.
+    pub fn new_page(&mut self) {
+        if !self.pages[self.page()].ops.is_empty() {
+            self.pages.push(Page::default());
+        }
+        self.y = self.top();
+    }
+
+    /// Starts a new page if the remaining space is less than `height`.
+    pub fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.new_page();
+        }
+    }
+
+    /// Adds vertical space (except at the top of a page).
+    pub fn space(&mut self, height: f32) {
+        if self.y < self.top() {
+            self.y = (self.y - height).max(MARGIN);
+        }
+    }
+
+    pub fn push(&mut self, op: Op) {
+        let page = self.page();
+        self.pages[page].ops.push(op);
+    }
+
+    pub fn line_height(size: f32) -> f32 {
+        size * LINE_HEIGHT
+    }
+
+    /// Returns the width of a single line of text.
+    pub fn text_width(&self, text: &str, style: Style, size: f32) -> f32 {
+        self.fonts.width(text, style) * size / 1000.0
+    }
+
+    /// Breaks text into lines of at most `width` (only words that are wider
+    /// than a line exceed it).
+    pub fn wrap(&self, spans: &[Span], size: f32, width: f32) -> Vec<Line> {
+        let scale = size / 1000.0;
+        let mut lines = Vec::new();
+        let mut line = Line::default();
+        // whether the last word is followed by a space, and whether it is
+        // still open (the next span continues it)
+        let mut space = false;
+        let mut open = false;
+        for span in spans {
+            let space_width = self.fonts.width(" ", span.style) * scale;
+            for (i, part) in span.text.split(' ').enumerate() {
+                if i > 0 {
+                    space = true;
+                    open = false;
+                }
+                if part.is_empty() {
+                    continue;
+                }
+                let runs = self.fonts.shape(part, span.style);
+                let word_width: f32 = runs.iter().map(|run| run.width * scale).sum();
+                let mut x = line.width;
+                if !line.runs.is_empty() && !open {
+                    let gap = if space { space_width } else { 0.0 };
+                    if line.width + gap + word_width > width {
+                        lines.push(mem::take(&mut line));
+                        x = 0.0;
+                    } else {
+                        x += gap;
+                    }
+                }
+                for run in runs {
+                    let run_width = run.width * scale;
+                    line.runs.push((x, run));
+                    x += run_width;
+                }
+                line.width = x;
+                space = false;
+                open = true;
+            }
+        }
+        if !line.runs.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Draws a line with its top at the current position, without moving it.
+    pub fn draw_line(&mut self, line: &Line, x: f32, size: f32, gray: f32) {
+        let y = self.y - size * BASELINE;
+        for (offset, run) in &line.runs {
+            self.push(Op::Text {
+                x: x + offset,
+                y,
+                size,
+                gray,
+                run: run.clone(),
+            });
+        }
+    }
+
+    /// Draws wrapped text, breaking pages between lines.
+    pub fn text(&mut self, spans: &[Span], size: f32, x: f32, width: f32, gray: f32) {
+        let line_height = Self::line_height(size);
+        for line in self.wrap(spans, size, width) {
+            self.ensure(line_height);
+            self.draw_line(&line, x, size, gray);
+            self.y -= line_height;
+        }
+    }
+
+    /// Draws a block of lines that must not be split across pages.
+    pub fn block(&mut self, lines: &[Line], size: f32, x: f32, gray: f32) {
+        let line_height = Self::line_height(size);
+        self.ensure(line_height * lines.len() as f32);
+        let top = self.y;
+        for line in lines {
+            self.draw_line(line, x, size, gray);
+            self.y -= line_height;
+        }
+        self.y = top;
+    }
+
+    /// Moves the current position down.
+    pub fn advance(&mut self, height: f32) {
+        self.y -= height;
+    }
+
+    pub fn image(&mut self, index: usize, width: f32, height: f32) {
+        self.ensure(height);
+        let x = MARGIN + (self.content_width() - width) / 2.0;
+        self.push(Op::Image {
+            x,
+            y: self.y - height,
+            width,
+            height,
+            index,
+        });
+        self.y -= height;
+    }
+
+    pub fn rule(&mut self) {
+        let (x, y, width) = (MARGIN, self.y, self.content_width());
+        self.push(Op::Rule { x, y, width });
+    }
+
+    /// Adds centered page numbers to the bottom of all pages, except the
+    /// first `skip` ones.
+    pub fn number_pages(&mut self, skip: usize, size: f32) {
+        for index in skip..self.pages.len() {
+            let number = (index + 1).to_string();
+            let line = self.wrap(&[Span::regular(&number)], size, f32::MAX);
+            let x = (self.width - line[0].width) / 2.0;
+            let y = MARGIN / 2.0;
+            for (offset, run) in line.into_iter().flat_map(|line| line.runs) {
+                self.pages[index].ops.push(Op::Text {
+                    x: x + offset,
+                    y,
+                    size,
+                    gray: 0.4,
+                    run,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+
+    #[test]
+    fn test_wrap() -> Result<()> {
+        let fonts = Fonts::load()?;
+        let composer = Composer::new(&fonts, (595.0, 842.0));
+        let text = "Bring the water to a boil and add salt.";
+        let line = &composer.wrap(&[Span::regular(text)], 10.0, f32::MAX)[0];
+        let width = composer.text_width(text, Style::Regular, 10.0);
+        assert!((line.width - width).abs() < 0.01);
+
+        let lines = composer.wrap(&[Span::regular(text)], 10.0, width / 2.0);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.width <= width / 2.0));
+
+        // spans without a space in between are not separated
+        let spans = [
+            Span::bold("200 g"),
+            Span::regular("flour"),
+            Span::regular(" (sifted)"),
+        ];
+        let lines = composer.wrap(&spans, 10.0, f32::MAX);
+        let bold = composer.text_width("200 g", Style::Bold, 10.0);
+        let regular = composer.text_width("flour (sifted)", Style::Regular, 10.0);
+        assert!((lines[0].width - bold - regular).abs() < 0.01);
+        Ok(())
+    }
+
+    #[test]
+    fn test_page_breaks() -> Result<()> {
+        let fonts = Fonts::load()?;
+        let mut composer = Composer::new(&fonts, (595.0, 842.0));
+        let text = "word ".repeat(2000);
+        composer.text(&[Span::regular(&text)], 10.0, MARGIN, 480.0, 0.0);
+        assert!(composer.page() > 1);
+        composer.number_pages(0, 8.0);
+        for page in composer.into_pages() {
+            for op in page.ops {
+                if let Op::Text { y, .. } = op {
+                    assert!((MARGIN / 2.0..842.0 - MARGIN).contains(&y));
+                }
+            }
+        }
+        Ok(())
+    }
+}
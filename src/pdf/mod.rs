@@ -0,0 +1,586 @@
+//! PDF export of recipes and cookbooks, using the bundled Merriweather fonts.
+
+mod font;
+mod layout;
+mod woff2;
+mod write;
+
+use std::{io::Cursor, path::Path};
+
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, codecs::jpeg::JpegEncoder};
+use serde::Deserialize;
+
+use crate::{
+    error::{Error, Result},
+    recipe::{
+        Recipe,
+        image_ref::remove_image_refs,
+        ingredient::Ingredient,
+        list::List,
+        markdown::{self, Inline},
+        metadata::Source,
+    },
+};
+
+use self::{
+    font::{Fonts, Style},
+    layout::{Composer, Line, MARGIN, Op, Span},
+    write::{Bookmark, Image},
+};
+
+const TITLE_SIZE: f32 = 22.0;
+const HEADING_SIZE: f32 = 13.0;
+const SUBHEADING_SIZE: f32 = 10.5;
+const TEXT_SIZE: f32 = 10.0;
+const META_SIZE: f32 = 9.0;
+const PAGE_NUMBER_SIZE: f32 = 8.0;
+const META_GRAY: f32 = 0.35;
+const COLUMN_GAP: f32 = 20.0;
+const MAX_IMAGE_HEIGHT: f32 = 260.0;
+// images are downscaled to this width (in pixels) before they are embedded
+const MAX_IMAGE_WIDTH: u32 = 1200;
+const IMAGE_QUALITY: u8 = 85;
+
+/// Page size of PDF files.
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    /// Width and height in points
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            Self::A4 => (595.0, 842.0),
+            Self::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+/// Texts of PDF files (English by default).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct Labels {
+    pub yields: String,
+    pub time: String,
+    pub tags: String,
+    pub source: String,
+    pub ingredients: String,
+    pub instructions: String,
+    pub notes: String,
+    pub contents: String,
+    /// Title of cookbooks
+    pub cookbook: String,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            yields: "Yield".into(),
+            time: "Time".into(),
+            tags: "Tags".into(),
+            source: "Source".into(),
+            ingredients: "Ingredients".into(),
+            instructions: "Instructions".into(),
+            notes: "Notes".into(),
+            contents: "Contents".into(),
+            cookbook: "Cookbook".into(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PdfOptions {
+    pub page_size: PageSize,
+    /// Render inline Markdown of instructions and notes, otherwise only
+    /// recipe references are replaced with their titles
+    pub inline_markdown: bool,
+    pub labels: Labels,
+}
+
+/// Renders recipes to PDF files.
+pub struct Renderer {
+    fonts: Fonts,
+    options: PdfOptions,
+}
+
+impl Renderer {
+    pub fn new(options: PdfOptions) -> Result<Self> {
+        Ok(Self {
+            fonts: Fonts::load()?,
+            options,
+        })
+    }
+
+    /// Renders a recipe with an optional image file.
+    pub fn render_recipe(&self, recipe: &Recipe, image: Option<&Path>) -> Result<Vec<u8>> {
+        let images: Vec<_> = image
+            .map(load_image)
+            .transpose()?
+            .flatten()
+            .into_iter()
+            .collect();
+        let mut composer = Composer::new(&self.fonts, self.page_size());
+        self.compose_recipe(
+            &mut composer,
+            recipe,
+            images.first().map(|image| (0, image)),
+        );
+        composer.number_pages(0, PAGE_NUMBER_SIZE);
+        Ok(write::write(
+            &recipe.title,
+            self.page_size(),
+            &composer.into_pages(),
+            &self.fonts,
+            &images,
+            &[],
+        ))
+    }
+
+    /// Renders recipes (with optional image files) in the given order to a
+    /// cookbook with a title page, a table of contents and bookmarks.
+    pub fn render_cookbook<P: AsRef<Path>>(
+        &self,
+        recipes: &[(Recipe, Option<P>)],
+    ) -> Result<Vec<u8>> {
+        let mut images = Vec::new();
+        let mut image_indices = Vec::new();
+        for (_, path) in recipes {
+            let image = path.as_ref().map(|path| load_image(path.as_ref()));
+            if let Some(image) = image.transpose()?.flatten() {
+                images.push(image);
+                image_indices.push(Some(images.len() - 1));
+            } else {
+                image_indices.push(None);
+            }
+        }
+        let compose_recipes = |composer: &mut Composer| {
+            recipes
+                .iter()
+                .zip(&image_indices)
+                .map(|((recipe, _), index)| {
+                    composer.new_page();
+                    let page = composer.page();
+                    let image = index.map(|index| (index, &images[index]));
+                    self.compose_recipe(composer, recipe, image);
+                    page
+                })
+                .collect::<Vec<_>>()
+        };
+        let titles: Vec<_> = recipes
+            .iter()
+            .map(|(recipe, _)| recipe.title.as_str())
+            .collect();
+
+        // a first pass determines the pages of the recipes (relative to the
+        // first one) and the number of contents pages
+        let pages = compose_recipes(&mut Composer::new(&self.fonts, self.page_size()));
+        let mut contents = Composer::new(&self.fonts, self.page_size());
+        self.compose_contents(&mut contents, &titles, &pages);
+        let offset = contents.page() + 2;
+        let pages: Vec<_> = pages.into_iter().map(|page| page + offset).collect();
+
+        let mut composer = Composer::new(&self.fonts, self.page_size());
+        self.compose_title_page(&mut composer);
+        composer.new_page();
+        self.compose_contents(&mut composer, &titles, &pages);
+        compose_recipes(&mut composer);
+        composer.number_pages(1, PAGE_NUMBER_SIZE);
+        let bookmarks: Vec<_> = titles
+            .iter()
+            .zip(pages)
+            .map(|(title, page)| Bookmark {
+                title: title.to_string(),
+                page,
+            })
+            .collect();
+        Ok(write::write(
+            &self.options.labels.cookbook,
+            self.page_size(),
+            &composer.into_pages(),
+            &self.fonts,
+            &images,
+            &bookmarks,
+        ))
+    }
+
+    fn page_size(&self) -> (f32, f32) {
+        self.options.page_size.dimensions()
+    }
+
+    fn compose_title_page(&self, composer: &mut Composer) {
+        let (_, height) = self.page_size();
+        let title = &self.options.labels.cookbook;
+        let size = TITLE_SIZE * 1.5;
+        composer.advance(height * 0.3);
+        let width = composer.content_width();
+        for line in composer.wrap(&[Span::bold(title)], size, width) {
+            composer.draw_line(&line, MARGIN + (width - line.width) / 2.0, size, 0.0);
+            composer.advance(Composer::line_height(size));
+        }
+    }
+
+    /// Lists the recipe titles with their page numbers, linked to the pages.
+    fn compose_contents(&self, composer: &mut Composer, titles: &[&str], pages: &[usize]) {
+        let width = composer.content_width();
+        composer.text(
+            &[Span::bold(&self.options.labels.contents)],
+            TITLE_SIZE,
+            MARGIN,
+            width,
+            0.0,
+        );
+        composer.space(HEADING_SIZE);
+        let number_width = composer.text_width("0000", Style::Regular, TEXT_SIZE);
+        let line_height = Composer::line_height(TEXT_SIZE);
+        for (title, &page) in titles.iter().zip(pages) {
+            let lines = composer.wrap(&[Span::regular(title)], TEXT_SIZE, width - number_width);
+            composer.block(&lines, TEXT_SIZE, MARGIN, 0.0);
+            let number = (page + 1).to_string();
+            let number_line = &composer.wrap(&[Span::regular(&number)], TEXT_SIZE, f32::MAX)[0];
+            composer.draw_line(
+                number_line,
+                MARGIN + width - number_line.width,
+                TEXT_SIZE,
+                0.0,
+            );
+            let height = line_height * lines.len() as f32;
+            let y = composer.y();
+            composer.push(Op::Link {
+                rect: [MARGIN, y - height, MARGIN + width, y],
+                page,
+            });
+            composer.advance(height + 2.0);
+        }
+    }
+
+    fn compose_recipe(
+        &self,
+        composer: &mut Composer,
+        recipe: &Recipe,
+        image: Option<(usize, &Image)>,
+    ) {
+        let labels = &self.options.labels;
+        let inline_markdown = self.options.inline_markdown;
+        let width = composer.content_width();
+        composer.text(&[Span::bold(&recipe.title)], TITLE_SIZE, MARGIN, width, 0.0);
+        let metadata = self.metadata_line(recipe);
+        composer.text(
+            &[Span::regular(&metadata)],
+            META_SIZE,
+            MARGIN,
+            width,
+            META_GRAY,
+        );
+        composer.space(6.0);
+        composer.rule();
+        if let Some((index, image)) = image {
+            composer.space(12.0);
+            let mut size = (width, width * image.height as f32 / image.width as f32);
+            if size.1 > MAX_IMAGE_HEIGHT {
+                size = (size.0 * MAX_IMAGE_HEIGHT / size.1, MAX_IMAGE_HEIGHT);
+            }
+            composer.image(index, size.0, size.1);
+        }
+        if !recipe.description.is_empty() {
+            composer.space(6.0);
+            for paragraph in &recipe.description {
+                composer.space(TEXT_SIZE * 0.5);
+                for line in paragraph.lines() {
+                    composer.text(&[Span::regular(line)], TEXT_SIZE, MARGIN, width, 0.0);
+                }
+            }
+        }
+
+        heading(composer, &labels.ingredients);
+        match &recipe.ingredients {
+            List::Basic(items) => ingredients(composer, items),
+            List::Sectioned(sections) => {
+                for section in sections {
+                    subheading(composer, &section.name);
+                    ingredients(composer, &section.items);
+                }
+            }
+        }
+
+        heading(composer, &labels.instructions);
+        let mut number = 0;
+        let mut instructions = |composer: &mut Composer, items: &[String]| {
+            for item in items {
+                let runs = markdown_runs(&remove_image_refs(item), inline_markdown);
+                if !runs.is_empty() {
+                    number += 1;
+                    list_item(composer, &format!("{number}."), &runs);
+                }
+            }
+        };
+        match &recipe.instructions {
+            List::Basic(items) => instructions(composer, items),
+            List::Sectioned(sections) => {
+                for section in sections {
+                    subheading(composer, &section.name);
+                    instructions(composer, &section.items);
+                }
+            }
+        }
+
+        if !recipe.notes.is_empty() {
+            heading(composer, &labels.notes);
+            for note in &recipe.notes {
+                list_item(composer, "–", &markdown_runs(note, inline_markdown));
+            }
+        }
+
+        if let Some(source) = &recipe.metadata.source {
+            let source = match source {
+                Source::Author(author) => author.clone(),
+                Source::Book(book) => book.clone(),
+                Source::Link(link) => format!("{} ({})", link.name, link.url),
+            };
+            let source = format!("{}: {source}", labels.source);
+            composer.space(HEADING_SIZE);
+            composer.text(
+                &[Span::regular(&source)],
+                META_SIZE,
+                MARGIN,
+                width,
+                META_GRAY,
+            );
+        }
+    }
+
+    /// Returns yield, time and tags of a recipe.
+    fn metadata_line(&self, recipe: &Recipe) -> String {
+        let labels = &self.options.labels;
+        let metadata = &recipe.metadata;
+        let mut parts = vec![format!("{}: {}", labels.yields, metadata.yields)];
+        if let Some(duration) = &metadata.duration {
+            parts.push(format!("{}: {duration}", labels.time));
+        }
+        if !metadata.tags.is_empty() {
+            parts.push(format!("{}: {}", labels.tags, metadata.tags.join(", ")));
+        }
+        parts.join("  ·  ")
+    }
+}
+
+fn heading(composer: &mut Composer, text: &str) {
+    composer.space(HEADING_SIZE * 1.5);
+    // keep the heading together with the first line of the following content
+    composer.ensure(Composer::line_height(HEADING_SIZE) + Composer::line_height(TEXT_SIZE) * 2.0);
+    let width = composer.content_width();
+    composer.text(&[Span::bold(text)], HEADING_SIZE, MARGIN, width, 0.0);
+    composer.space(2.0);
+}
+
+fn subheading(composer: &mut Composer, text: &str) {
+    composer.space(SUBHEADING_SIZE * 0.5);
+    composer.ensure(Composer::line_height(SUBHEADING_SIZE) + Composer::line_height(TEXT_SIZE));
+    let width = composer.content_width();
+    composer.text(
+        &[Span::bold(text)],
+        SUBHEADING_SIZE,
+        MARGIN,
+        width,
+        META_GRAY,
+    );
+}
+
+/// Lays out ingredients in two columns (row by row), with bold quantities.
+fn ingredients(composer: &mut Composer, items: &[Ingredient]) {
+    let column_width = (composer.content_width() - COLUMN_GAP) / 2.0;
+    let line_height = Composer::line_height(TEXT_SIZE);
+    for row in items.chunks(2) {
+        let columns: Vec<Vec<Line>> = row
+            .iter()
+            .map(|ingredient| {
+                let (quantity, name) = ingredient_texts(ingredient);
+                let spans = [Span::bold(&quantity), Span::regular(&name)];
+                composer.wrap(&spans, TEXT_SIZE, column_width)
+            })
+            .collect();
+        let line_count = columns.iter().map(Vec::len).max().unwrap_or_default();
+        composer.ensure(line_height * line_count as f32);
+        for (index, lines) in columns.iter().enumerate() {
+            let x = MARGIN + index as f32 * (column_width + COLUMN_GAP);
+            composer.block(lines, TEXT_SIZE, x, 0.0);
+        }
+        composer.advance(line_height * line_count as f32 + 2.0);
+    }
+}
+
+/// Replaces recipe references (`@[Title]`) with their titles.
+fn without_references(text: &str) -> String {
+    Inline::plain_text(&markdown::parse_references(text))
+}
+
+/// Renders the inline Markdown (and recipe references) of `text` to runs of
+/// regular and bold text, with strong emphasis in bold. Other formatting and
+/// link URLs are dropped, like in plain text. Without `inline_markdown` only
+/// recipe references are replaced.
+fn markdown_runs(text: &str, inline_markdown: bool) -> Vec<(String, Style)> {
+    fn push(runs: &mut Vec<(String, Style)>, inlines: &[Inline], style: Style) {
+        for inline in inlines {
+            match inline {
+                Inline::Strong(content) => push(runs, content, Style::Bold),
+                Inline::Emphasis(content) | Inline::Link { content, .. } => {
+                    push(runs, content, style);
+                }
+                Inline::Text(text) | Inline::Code(text) | Inline::Reference(text) => {
+                    match runs.last_mut() {
+                        Some((last, last_style)) if *last_style == style => last.push_str(text),
+                        _ => runs.push((text.clone(), style)),
+                    }
+                }
+            }
+        }
+    }
+    let mut runs = Vec::new();
+    let inlines = if inline_markdown {
+        markdown::parse(text)
+    } else {
+        markdown::parse_references(text)
+    };
+    push(&mut runs, &inlines, Style::Regular);
+    runs
+}
+
+/// Returns the quantity (with unit) and the rest of an ingredient.
+fn ingredient_texts(ingredient: &Ingredient) -> (String, String) {
+    let mut name = without_references(&ingredient.name);
+    if let Some(kind) = &ingredient.kind {
+        name = format!("{name}, {kind}");
+    }
+    let Some(quantity) = &ingredient.quantity else {
+        return (String::new(), name);
+    };
+    if let Some(note) = &quantity.note {
+        name = format!("{name} ({note})");
+    }
+    let value = match &quantity.unit {
+        Some(unit) => format!("{} {unit}", quantity.value),
+        None => quantity.value.to_string(),
+    };
+    (value, format!(" {name}"))
+}
+
+/// Lays out a paragraph with a hanging marker (e.g. a number).
+fn list_item(composer: &mut Composer, marker: &str, runs: &[(String, Style)]) {
+    const INDENT: f32 = 18.0;
+    let width = composer.content_width();
+    let marker = composer.wrap(&[Span::bold(marker)], TEXT_SIZE, f32::MAX);
+    composer.ensure(Composer::line_height(TEXT_SIZE));
+    composer.draw_line(&marker[0], MARGIN, TEXT_SIZE, 0.0);
+    let spans: Vec<_> = runs
+        .iter()
+        .map(|(text, style)| Span {
+            text,
+            style: *style,
+        })
+        .collect();
+    composer.text(&spans, TEXT_SIZE, MARGIN + INDENT, width - INDENT, 0.0);
+    composer.space(4.0);
+}
+
+/// Decodes an image, downscales it and encodes it as JPEG. Returns `None`
+/// for files that are no raster images (e.g. SVG).
+fn load_image(path: &Path) -> Result<Option<Image>> {
+    if ImageFormat::from_path(path).is_err() {
+        return Ok(None);
+    }
+    let invalid = |error| Error::InvalidImage(path.into(), error);
+    let mut decoder = ImageReader::open(path)?
+        .with_guessed_format()?
+        .into_decoder()
+        .map_err(invalid)?;
+    let orientation = decoder.orientation().map_err(invalid)?;
+    let mut image = DynamicImage::from_decoder(decoder).map_err(invalid)?;
+    image.apply_orientation(orientation);
+    if image.width() > MAX_IMAGE_WIDTH {
+        image = image.thumbnail(MAX_IMAGE_WIDTH, u32::MAX);
+    }
+    let image = DynamicImage::ImageRgb8(image.to_rgb8());
+    let mut data = Vec::new();
+    image
+        .write_with_encoder(JpegEncoder::new_with_quality(
+            Cursor::new(&mut data),
+            IMAGE_QUALITY,
+        ))
+        .map_err(invalid)?;
+    Ok(Some(Image {
+        data,
+        width: image.width(),
+        height: image.height(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    const RECIPE: &str = "Borscht\n\nYield: 4\nTime: 1h 30m\nTags: soup\n\n\
+        Ingredients\n  - beetroot: 500 g (peeled)\n  - salt\n\n\
+        Instructions\n  - Cook everything. @image(1)\n\nNotes\n  - Serve with sour cream.\n";
+
+    fn page_count(pdf: &[u8]) -> usize {
+        String::from_utf8_lossy(pdf)
+            .matches("/Type /Page\n")
+            .count()
+    }
+
+    #[test]
+    fn test_markdown_runs() {
+        let text = "Serve **hot *and* fresh** with [cream](https://x) and @[Sauce]";
+        let runs = markdown_runs(text, true);
+        assert_eq!(
+            runs,
+            [
+                ("Serve ".into(), Style::Regular),
+                ("hot and fresh".into(), Style::Bold),
+                (" with cream and Sauce".into(), Style::Regular),
+            ]
+        );
+        assert_eq!(
+            markdown_runs("Cut 2 * 3 * 4 cm cubes, see @[Sauce]", false),
+            [("Cut 2 * 3 * 4 cm cubes, see Sauce".into(), Style::Regular)]
+        );
+        assert!(markdown_runs("", true).is_empty());
+    }
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let image = temp_dir.path().join("image.png");
+        RgbImage::new(40, 20).save(&image).unwrap();
+        let renderer = Renderer::new(PdfOptions::default())?;
+        let recipe = Recipe::parse_from(RECIPE.as_bytes())?;
+
+        let pdf = renderer.render_recipe(&recipe, Some(&image))?;
+        assert!(pdf.starts_with(b"%PDF-"));
+        assert_eq!(page_count(&pdf), 1);
+        let text = String::from_utf8_lossy(&pdf);
+        assert!(text.contains("/Subtype /Image"));
+        assert!(text.contains("/BaseFont /Merriweather-Bold-latin"));
+        // only used fonts are embedded
+        assert!(!text.contains("Merriweather-Regular-cyrillic"));
+
+        let recipes = [
+            (Recipe::parse_from(RECIPE.as_bytes())?, Some(image)),
+            (Recipe::parse_from(RECIPE.as_bytes())?, None),
+        ];
+        let pdf = renderer.render_cookbook(&recipes)?;
+        // title page, contents and a page per recipe
+        assert_eq!(page_count(&pdf), 4);
+        let text = String::from_utf8_lossy(&pdf);
+        assert_eq!(text.matches("/Subtype /Link").count(), 2);
+        assert!(text.contains("/Type /Outlines"));
+        Ok(())
+    }
+}
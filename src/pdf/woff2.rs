@@ -0,0 +1,517 @@
+//! Minimal WOFF2 decoder, converting the bundled web fonts to TrueType fonts
+//! that can be embedded into PDF files.
+//!
+//! Supports the `glyf`/`loca` transform, but neither font collections nor
+//! the transformed `hmtx` table.
+
+use std::io::Read;
+
+use crate::error::{Error, Result};
+
+const SIGNATURE: u32 = 0x774f_4632; // "wOF2"
+const HEADER_SIZE: usize = 48;
+const HEAD_CHECKSUM_OFFSET: usize = 8;
+const CHECKSUM_MAGIC: u32 = 0xb1b0_afba;
+
+const KNOWN_TAGS: [&[u8; 4]; 63] = [
+    b"cmap", b"head", b"hhea", b"hmtx", b"maxp", b"name", b"OS/2", b"post", b"cvt ", b"fpgm",
+    b"glyf", b"loca", b"prep", b"CFF ", b"VORG", b"EBDT", b"EBLC", b"gasp", b"hdmx", b"kern",
+    b"LTSH", b"PCLT", b"VDMX", b"vhea", b"vmtx", b"BASE", b"GDEF", b"GPOS", b"GSUB", b"EBSC",
+    b"JSTF", b"MATH", b"CBDT", b"CBLC", b"COLR", b"CPAL", b"SVG ", b"sbix", b"acnt", b"avar",
+    b"bdat", b"bloc", b"bsln", b"cvar", b"fdsc", b"feat", b"fmtx", b"fvar", b"gvar", b"hsty",
+    b"just", b"lcar", b"mort", b"morx", b"opbd", b"prop", b"trak", b"Zapf", b"Silf", b"Glat",
+    b"Gloc", b"Feat", b"Sill",
+];
+
+// simple glyph flags
+const ON_CURVE: u8 = 0x01;
+const X_SHORT: u8 = 0x02;
+const Y_SHORT: u8 = 0x04;
+const X_SAME_OR_POSITIVE: u8 = 0x10;
+const Y_SAME_OR_POSITIVE: u8 = 0x20;
+
+// composite glyph flags
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+const WE_HAVE_INSTRUCTIONS: u16 = 0x0100;
+
+struct Table {
+    tag: [u8; 4],
+    data: Vec<u8>,
+}
+
+/// Converts WOFF2 font data to a TrueType font.
+pub(crate) fn decode(data: &[u8]) -> Result<Vec<u8>> {
+    let mut header = Reader::new(data);
+    if header.u32()? != SIGNATURE {
+        return Err(invalid("missing WOFF2 signature"));
+    }
+    let flavor = header.u32()?;
+    header.skip(4)?; // length
+    let table_count = header.u16()?;
+    header.skip(6)?; // reserved, total sfnt size
+    let compressed_size = header.u32()? as usize;
+    let mut directory = Reader::new(data.get(HEADER_SIZE..).ok_or_else(too_short)?);
+    let mut entries = Vec::with_capacity(table_count.into());
+    for _ in 0..table_count {
+        let flags = directory.u8()?;
+        let tag = match usize::from(flags & 0x3f) {
+            63 => directory.bytes(4)?.try_into().unwrap(),
+            index => *KNOWN_TAGS[index],
+        };
+        let version = flags >> 6;
+        let length = directory.base128()? as usize;
+        let is_transformed = if matches!(&tag, b"glyf" | b"loca") {
+            version != 3
+        } else {
+            version != 0
+        };
+        let transform_length = if is_transformed {
+            Some(directory.base128()? as usize)
+        } else {
+            None
+        };
+        if is_transformed && !matches!(&tag, b"glyf" | b"loca") {
+            return Err(invalid("unsupported table transform"));
+        }
+        entries.push((tag, length, transform_length));
+    }
+    if flavor == u32::from_be_bytes(*b"ttcf") {
+        return Err(invalid("font collections are not supported"));
+    }
+    let compressed_start = HEADER_SIZE + directory.position;
+    let compressed = data
+        .get(compressed_start..compressed_start + compressed_size)
+        .ok_or_else(too_short)?;
+    let mut stream = Vec::new();
+    brotli_decompressor::Decompressor::new(compressed, 4096).read_to_end(&mut stream)?;
+    let mut stream = Reader::new(&stream);
+    let mut tables = Vec::with_capacity(entries.len());
+    let mut glyf = None;
+    for (tag, length, transform_length) in entries {
+        let data = stream.bytes(transform_length.unwrap_or(length))?;
+        match (&tag, transform_length) {
+            (b"glyf", Some(_)) => glyf = Some(reconstruct_glyf(data)?),
+            // reconstructed from the glyf table
+            (b"loca", Some(_)) => {}
+            _ => tables.push(Table {
+                tag,
+                data: data.to_vec(),
+            }),
+        }
+    }
+    if let Some((glyf, loca)) = glyf {
+        tables.push(Table {
+            tag: *b"glyf",
+            data: glyf,
+        });
+        tables.push(Table {
+            tag: *b"loca",
+            data: loca,
+        });
+    }
+    Ok(write_sfnt(flavor, tables))
+}
+
+/// Returns the `glyf` and `loca` table.
+fn reconstruct_glyf(data: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut header = Reader::new(data);
+    header.skip(2)?; // reserved
+    header.skip(2)?; // option flags (overlap bitmap is ignored)
+    let glyph_count = usize::from(header.u16()?);
+    let index_format = header.u16()?;
+    let mut sizes = [0; 7];
+    for size in &mut sizes {
+        *size = header.u32()? as usize;
+    }
+    let mut offset = header.position;
+    let mut streams = sizes.map(|size| {
+        let stream = data.get(offset..offset + size).unwrap_or_default();
+        offset += size;
+        Reader::new(stream)
+    });
+    if offset > data.len() {
+        return Err(too_short());
+    }
+    let [
+        n_contours,
+        n_points,
+        flags,
+        glyphs,
+        composites,
+        bboxes,
+        instructions,
+    ] = &mut streams;
+    let bbox_bitmap = bboxes.bytes(((glyph_count + 31) >> 5) << 2)?;
+    let mut glyf = Vec::new();
+    let mut offsets = Vec::with_capacity(glyph_count + 1);
+    for index in 0..glyph_count {
+        offsets.push(glyf.len());
+        let has_bbox = bbox_bitmap[index >> 3] & (0x80 >> (index & 7)) != 0;
+        let contour_count = n_contours.i16()?;
+        if contour_count == 0 {
+            continue;
+        }
+        let mut glyph = Writer::default();
+        glyph.i16(contour_count);
+        if contour_count < 0 {
+            if !has_bbox {
+                return Err(invalid("composite glyph without bounding box"));
+            }
+            glyph.bytes(bboxes.bytes(8)?);
+            let mut has_instructions = false;
+            loop {
+                let start = composites.position;
+                let flags = composites.u16()?;
+                has_instructions |= flags & WE_HAVE_INSTRUCTIONS != 0;
+                let mut length = 2 + if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                    4
+                } else {
+                    2
+                };
+                if flags & WE_HAVE_A_SCALE != 0 {
+                    length += 2;
+                } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                    length += 4;
+                } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                    length += 8;
+                }
+                composites.skip(length)?;
+                glyph.bytes(&composites.data[start..composites.position]);
+                if flags & MORE_COMPONENTS == 0 {
+                    break;
+                }
+            }
+            if has_instructions {
+                let length = glyphs.u255()?;
+                glyph.u16(length);
+                glyph.bytes(instructions.bytes(length.into())?);
+            }
+        } else {
+            let mut end_points = Vec::with_capacity(contour_count as usize);
+            let mut point_count = 0;
+            for _ in 0..contour_count {
+                point_count += usize::from(n_points.u255()?);
+                end_points.push(point_count - 1);
+            }
+            let mut points = Vec::with_capacity(point_count);
+            let (mut x, mut y) = (0i32, 0i32);
+            for _ in 0..point_count {
+                let flag = flags.u8()?;
+                let (dx, dy) = decode_triplet(flag & 0x7f, glyphs)?;
+                x += dx;
+                y += dy;
+                points.push((x, y, flag & 0x80 == 0));
+            }
+            if has_bbox {
+                glyph.bytes(bboxes.bytes(8)?);
+            } else {
+                let (min_x, max_x) = min_max(points.iter().map(|point| point.0));
+                let (min_y, max_y) = min_max(points.iter().map(|point| point.1));
+                for value in [min_x, min_y, max_x, max_y] {
+                    glyph.i16(value as i16);
+                }
+            }
+            for end_point in end_points {
+                glyph.u16(end_point as u16);
+            }
+            let instruction_length = glyphs.u255()?;
+            glyph.u16(instruction_length);
+            glyph.bytes(instructions.bytes(instruction_length.into())?);
+            write_points(&mut glyph, &points);
+        }
+        glyf.extend(glyph.0);
+        // short offsets are divided by 2 and all glyphs are aligned to 4 bytes
+        glyf.resize(glyf.len().next_multiple_of(4), 0);
+    }
+    offsets.push(glyf.len());
+    let mut loca = Writer::default();
+    for offset in offsets {
+        if index_format == 0 {
+            loca.u16((offset / 2) as u16);
+        } else {
+            loca.u32(offset as u32);
+        }
+    }
+    Ok((glyf, loca.0))
+}
+
+fn decode_triplet(flag: u8, glyphs: &mut Reader) -> Result<(i32, i32)> {
+    let with_sign = |flag: u8, value: i32| if flag & 1 != 0 { value } else { -value };
+    let flag_value = i32::from(flag);
+    Ok(match flag {
+        0..10 => (
+            0,
+            with_sign(flag, ((flag_value & 14) << 7) + glyphs.i32_u8()?),
+        ),
+        10..20 => (
+            with_sign(flag, (((flag_value - 10) & 14) << 7) + glyphs.i32_u8()?),
+            0,
+        ),
+        20..84 => {
+            let b0 = flag_value - 20;
+            let b1 = glyphs.i32_u8()?;
+            (
+                with_sign(flag, 1 + (b0 & 0x30) + (b1 >> 4)),
+                with_sign(flag >> 1, 1 + ((b0 & 0x0c) << 2) + (b1 & 0x0f)),
+            )
+        }
+        84..120 => {
+            let b0 = flag_value - 84;
+            (
+                with_sign(flag, 1 + ((b0 / 12) << 8) + glyphs.i32_u8()?),
+                with_sign(flag >> 1, 1 + (((b0 % 12) >> 2) << 8) + glyphs.i32_u8()?),
+            )
+        }
+        120..124 => {
+            let b0 = glyphs.i32_u8()?;
+            let b1 = glyphs.i32_u8()?;
+            let b2 = glyphs.i32_u8()?;
+            (
+                with_sign(flag, (b0 << 4) + (b1 >> 4)),
+                with_sign(flag >> 1, ((b1 & 0x0f) << 8) + b2),
+            )
+        }
+        _ => {
+            let x = i32::from(glyphs.u16()?);
+            let y = i32::from(glyphs.u16()?);
+            (with_sign(flag, x), with_sign(flag >> 1, y))
+        }
+    })
+}
+
+/// Writes flags and coordinates of a simple glyph (without flag repetition).
+fn write_points(glyph: &mut Writer, points: &[(i32, i32, bool)]) {
+    let mut flags = Vec::with_capacity(points.len());
+    let mut xs = Writer::default();
+    let mut ys = Writer::default();
+    let (mut last_x, mut last_y) = (0, 0);
+    for &(x, y, on_curve) in points {
+        let mut flag = if on_curve { ON_CURVE } else { 0 };
+        for (delta, short, same_or_positive, coords) in [
+            (x - last_x, X_SHORT, X_SAME_OR_POSITIVE, &mut xs),
+            (y - last_y, Y_SHORT, Y_SAME_OR_POSITIVE, &mut ys),
+        ] {
+            if delta == 0 {
+                flag |= same_or_positive;
+            } else if delta.abs() < 256 {
+                flag |= short;
+                if delta > 0 {
+                    flag |= same_or_positive;
+                }
+                coords.0.push(delta.unsigned_abs() as u8);
+            } else {
+                coords.i16(delta as i16);
+            }
+        }
+        flags.push(flag);
+        (last_x, last_y) = (x, y);
+    }
+    glyph.bytes(&flags);
+    glyph.bytes(&xs.0);
+    glyph.bytes(&ys.0);
+}
+
+fn write_sfnt(flavor: u32, mut tables: Vec<Table>) -> Vec<u8> {
+    tables.sort_by_key(|table| table.tag);
+    let count = tables.len() as u16;
+    let entry_selector = count.ilog2() as u16;
+    let search_range = (1 << entry_selector) * 16;
+    let mut font = Writer::default();
+    font.u32(flavor);
+    font.u16(count);
+    font.u16(search_range);
+    font.u16(entry_selector);
+    font.u16(count * 16 - search_range);
+    let mut offset = 12 + 16 * tables.len();
+    let mut head_offset = None;
+    for table in &tables {
+        font.bytes(&table.tag);
+        font.u32(checksum(&table.data));
+        font.u32(offset as u32);
+        font.u32(table.data.len() as u32);
+        if &table.tag == b"head" {
+            head_offset = Some(offset);
+        }
+        offset += table.data.len().next_multiple_of(4);
+    }
+    for table in &tables {
+        font.bytes(&table.data);
+        font.0.resize(font.0.len().next_multiple_of(4), 0);
+    }
+    let mut font = font.0;
+    if let Some(offset) = head_offset.map(|offset| offset + HEAD_CHECKSUM_OFFSET)
+        && offset + 4 <= font.len()
+    {
+        font[offset..offset + 4].fill(0);
+        let adjustment = CHECKSUM_MAGIC.wrapping_sub(checksum(&font));
+        font[offset..offset + 4].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+fn min_max(values: impl Iterator<Item = i32>) -> (i32, i32) {
+    values.fold((i32::MAX, i32::MIN), |(min, max), value| {
+        (min.min(value), max.max(value))
+    })
+}
+
+fn invalid(message: &'static str) -> Error {
+    Error::InvalidFont(message)
+}
+
+fn too_short() -> Error {
+    invalid("unexpected end of data")
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + length)
+            .ok_or_else(too_short)?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize) -> Result<()> {
+        self.bytes(length).map(|_| ())
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn i32_u8(&mut self) -> Result<i32> {
+        self.u8().map(i32::from)
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn base128(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for _ in 0..5 {
+            let byte = self.u8()?;
+            value = value
+                .checked_mul(128)
+                .ok_or_else(|| invalid("invalid base128 number"))?
+                | u32::from(byte & 0x7f);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("invalid base128 number"))
+    }
+
+    fn u255(&mut self) -> Result<u16> {
+        const WORD_CODE: u8 = 253;
+        const ONE_MORE_BYTE_CODE_2: u8 = 254;
+        const ONE_MORE_BYTE_CODE_1: u8 = 255;
+        const LOWEST_U_CODE: u16 = 253;
+        Ok(match self.u8()? {
+            WORD_CODE => self.u16()?,
+            ONE_MORE_BYTE_CODE_1 => u16::from(self.u8()?) + LOWEST_U_CODE,
+            ONE_MORE_BYTE_CODE_2 => u16::from(self.u8()?) + LOWEST_U_CODE * 2,
+            code => code.into(),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_be_bytes());
+    }
+
+    fn i16(&mut self, value: i16) {
+        self.bytes(&value.to_be_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() -> Result<()> {
+        let file = crate::TEMPLATE_DIR
+            .get_file("html/static/fonts/merriweather/400/latin.woff2")
+            .unwrap();
+        let font = decode(file.contents())?;
+        let face = ttf_parser::Face::parse(&font, 0).unwrap();
+        let glyph = face.glyph_index('a').unwrap();
+        assert!(face.glyph_hor_advance(glyph).unwrap() > 0);
+        let bbox = face.glyph_bounding_box(glyph).unwrap();
+        assert!(bbox.width() > 0 && bbox.height() > 0);
+        // glyph outlines are reconstructed
+        struct Counter(usize);
+        impl ttf_parser::OutlineBuilder for Counter {
+            fn move_to(&mut self, _: f32, _: f32) {
+                self.0 += 1;
+            }
+            fn line_to(&mut self, _: f32, _: f32) {}
+            fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+            fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+            fn close(&mut self) {}
+        }
+        let mut counter = Counter(0);
+        let outline_bbox = face.outline_glyph(glyph, &mut counter).unwrap();
+        assert_eq!(outline_bbox, bbox);
+        assert_eq!(counter.0, 2);
+
+        // the outlines of all glyphs (including composite ones) match their bounding box
+        let fonts = crate::TEMPLATE_DIR
+            .get_dir("html/static/fonts/merriweather")
+            .unwrap();
+        for file in fonts.dirs().flat_map(|dir| dir.files()) {
+            let font = decode(file.contents())?;
+            let face = ttf_parser::Face::parse(&font, 0).unwrap();
+            for id in 0..face.number_of_glyphs() {
+                let glyph = ttf_parser::GlyphId(id);
+                let outline_bbox = face.outline_glyph(glyph, &mut Counter(0));
+                assert_eq!(outline_bbox, face.glyph_bounding_box(glyph));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+//! Serialization of laid out pages to a PDF file.
+
+use std::collections::BTreeMap;
+
+use miniz_oxide::deflate::compress_to_vec_zlib;
+use pdf_writer::{
+    Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr,
+    types::{ActionType, AnnotationType, CidFontType, FontFlags, SystemInfo, UnicodeCmap},
+};
+
+use super::{
+    font::Fonts,
+    layout::{Op, Page},
+};
+
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// An image encoded as JPEG.
+pub(crate) struct Image {
+    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A bookmark of the document outline.
+pub(crate) struct Bookmark {
+    pub title: String,
+    pub page: usize,
+}
+
+/// Allocates consecutive object ids.
+struct Refs(i32);
+
+impl Refs {
+    fn next(&mut self) -> Ref {
+        self.0 += 1;
+        Ref::new(self.0)
+    }
+}
+
+pub(crate) fn write(
+    title: &str,
+    (width, height): (f32, f32),
+    pages: &[Page],
+    fonts: &Fonts,
+    images: &[Image],
+    bookmarks: &[Bookmark],
+) -> Vec<u8> {
+    let mut refs = Refs(0);
+    let catalog_id = refs.next();
+    let page_tree_id = refs.next();
+    let info_id = refs.next();
+    let page_ids: Vec<_> = pages.iter().map(|_| (refs.next(), refs.next())).collect();
+    let image_ids: Vec<_> = images.iter().map(|_| refs.next()).collect();
+
+    // used glyphs (with their characters) by face
+    let mut glyphs: BTreeMap<usize, BTreeMap<u16, char>> = BTreeMap::new();
+    for op in pages.iter().flat_map(|page| &page.ops) {
+        if let Op::Text { run, .. } = op {
+            glyphs
+                .entry(run.face)
+                .or_default()
+                .extend(run.glyphs.iter().copied());
+        }
+    }
+    let font_ids: BTreeMap<usize, Ref> = glyphs.keys().map(|&face| (face, refs.next())).collect();
+
+    let mut pdf = Pdf::new();
+    pdf.document_info(info_id)
+        .title(TextStr(title))
+        .producer(TextStr(crate::APP_NAME));
+    let mut catalog = pdf.catalog(catalog_id);
+    catalog.pages(page_tree_id);
+    let outline_id = (!bookmarks.is_empty()).then(|| refs.next());
+    if let Some(outline_id) = outline_id {
+        catalog.outlines(outline_id);
+    }
+    catalog.finish();
+    pdf.pages(page_tree_id)
+        .kids(page_ids.iter().map(|&(page_id, _)| page_id))
+        .count(pages.len() as i32);
+
+    for (page, &(page_id, content_id)) in pages.iter().zip(&page_ids) {
+        let mut content = Content::new();
+        for op in &page.ops {
+            match op {
+                Op::Text {
+                    x,
+                    y,
+                    size,
+                    gray,
+                    run,
+                } => {
+                    let text: Vec<u8> = run
+                        .glyphs
+                        .iter()
+                        .flat_map(|(glyph, _)| glyph.to_be_bytes())
+                        .collect();
+                    content
+                        .set_fill_gray(*gray)
+                        .begin_text()
+                        .set_font(font_name(run.face).as_name(), *size)
+                        .next_line(*x, *y)
+                        .show(Str(&text))
+                        .end_text();
+                }
+                Op::Image {
+                    x,
+                    y,
+                    width,
+                    height,
+                    index,
+                } => {
+                    content
+                        .save_state()
+                        .transform([*width, 0.0, 0.0, *height, *x, *y])
+                        .x_object(image_name(*index).as_name())
+                        .restore_state();
+                }
+                Op::Rule { x, y, width } => {
+                    content
+                        .set_stroke_gray(0.6)
+                        .set_line_width(0.5)
+                        .move_to(*x, *y)
+                        .line_to(x + width, *y)
+                        .stroke();
+                }
+                Op::Link { .. } => {}
+            }
+        }
+        let content = compress_to_vec_zlib(&content.finish(), COMPRESSION_LEVEL);
+        pdf.stream(content_id, &content).filter(Filter::FlateDecode);
+
+        let mut page_writer = pdf.page(page_id);
+        page_writer
+            .media_box(Rect::new(0.0, 0.0, width, height))
+            .parent(page_tree_id)
+            .contents(content_id);
+        let mut resources = page_writer.resources();
+        let mut page_fonts = resources.fonts();
+        for (&face, &font_id) in &font_ids {
+            page_fonts.pair(font_name(face).as_name(), font_id);
+        }
+        page_fonts.finish();
+        let mut x_objects = resources.x_objects();
+        for (index, &image_id) in image_ids.iter().enumerate() {
+            x_objects.pair(image_name(index).as_name(), image_id);
+        }
+        x_objects.finish();
+        resources.finish();
+        let mut annotations = page_writer.annotations();
+        for op in &page.ops {
+            if let Op::Link { rect, page } = op {
+                annotations
+                    .push()
+                    .subtype(AnnotationType::Link)
+                    .rect(Rect::new(rect[0], rect[1], rect[2], rect[3]))
+                    .border(0.0, 0.0, 0.0, None)
+                    .action()
+                    .action_type(ActionType::GoTo)
+                    .destination()
+                    .page(page_ids[*page].0)
+                    .xyz(0.0, height, None);
+            }
+        }
+        annotations.finish();
+    }
+
+    for (image, &image_id) in images.iter().zip(&image_ids) {
+        let mut xobject = pdf.image_xobject(image_id, &image.data);
+        xobject.filter(Filter::DctDecode);
+        xobject.width(image.width as i32);
+        xobject.height(image.height as i32);
+        xobject.color_space().device_rgb();
+        xobject.bits_per_component(8);
+    }
+
+    for (face_index, glyphs) in &glyphs {
+        let face = &fonts.faces()[*face_index];
+        let (cid_id, descriptor_id, file_id, cmap_id) =
+            (refs.next(), refs.next(), refs.next(), refs.next());
+        let base_font = Name(face.name.as_bytes());
+        let system_info = SystemInfo {
+            registry: Str(b"Adobe"),
+            ordering: Str(b"Identity"),
+            supplement: 0,
+        };
+        pdf.type0_font(font_ids[face_index])
+            .base_font(base_font)
+            .encoding_predefined(Name(b"Identity-H"))
+            .descendant_font(cid_id)
+            .to_unicode(cmap_id);
+        let mut cid = pdf.cid_font(cid_id);
+        cid.subtype(CidFontType::Type2)
+            .base_font(base_font)
+            .system_info(system_info)
+            .font_descriptor(descriptor_id)
+            .cid_to_gid_map_predefined(Name(b"Identity"));
+        let mut widths = cid.widths();
+        for &glyph in glyphs.keys() {
+            widths.consecutive(glyph, [face.advance(glyph)]);
+        }
+        widths.finish();
+        cid.finish();
+        let [x_min, y_min, x_max, y_max] = face.bbox;
+        pdf.font_descriptor(descriptor_id)
+            .name(base_font)
+            .flags(FontFlags::SERIF | FontFlags::NON_SYMBOLIC)
+            .bbox(Rect::new(x_min, y_min, x_max, y_max))
+            .italic_angle(0.0)
+            .ascent(face.ascender)
+            .descent(face.descender)
+            .cap_height(face.cap_height)
+            .stem_v(80.0)
+            .font_file2(file_id);
+        let data = compress_to_vec_zlib(&face.data, COMPRESSION_LEVEL);
+        pdf.stream(file_id, &data)
+            .filter(Filter::FlateDecode)
+            .pair(Name(b"Length1"), face.data.len() as i32);
+        let mut cmap = UnicodeCmap::new(Name(b"Custom"), system_info);
+        for (&glyph, &char) in glyphs {
+            cmap.pair(glyph, char);
+        }
+        pdf.cmap(cmap_id, &cmap.finish());
+    }
+
+    if let Some(outline_id) = outline_id {
+        let item_ids: Vec<_> = bookmarks.iter().map(|_| refs.next()).collect();
+        pdf.outline(outline_id)
+            .first(item_ids[0])
+            .last(item_ids[item_ids.len() - 1])
+            .count(item_ids.len() as i32);
+        for (index, bookmark) in bookmarks.iter().enumerate() {
+            let mut item = pdf.outline_item(item_ids[index]);
+            item.title(TextStr(&bookmark.title)).parent(outline_id);
+            if let Some(&prev) = index.checked_sub(1).and_then(|index| item_ids.get(index)) {
+                item.prev(prev);
+            }
+            if let Some(&next) = item_ids.get(index + 1) {
+                item.next(next);
+            }
+            item.dest()
+                .page(page_ids[bookmark.page].0)
+                .xyz(0.0, height, None);
+        }
+    }
+    pdf.finish()
+}
+
+struct ResourceName(String);
+
+impl ResourceName {
+    fn as_name(&self) -> Name<'_> {
+        Name(self.0.as_bytes())
+    }
+}
+
+fn font_name(face: usize) -> ResourceName {
+    ResourceName(format!("F{face}"))
+}
+
+fn image_name(index: usize) -> ResourceName {
+    ResourceName(format!("Im{index}"))
+}
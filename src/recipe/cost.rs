@@ -0,0 +1,314 @@
+//! Cost estimation of recipes from a local price list.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    ops::Deref,
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::{
+    ParseFromStr, Recipe,
+    ingredient::{Quantity, QuantityValue},
+    synonyms::{Synonyms, normalize},
+};
+
+pub const PRICES_FILE_NAME: &str = "prices.toml";
+
+/// Units that can be converted into each other, by lowercase name, with
+/// their factor to the base unit of their dimension (gram or milliliter).
+const UNITS: &[(&str, Dimension, f64)] = &[
+    ("mg", Dimension::Mass, 0.001),
+    ("g", Dimension::Mass, 1.0),
+    ("kg", Dimension::Mass, 1000.0),
+    ("oz", Dimension::Mass, 28.35),
+    ("lb", Dimension::Mass, 453.6),
+    ("ml", Dimension::Volume, 1.0),
+    ("cl", Dimension::Volume, 10.0),
+    ("dl", Dimension::Volume, 100.0),
+    ("l", Dimension::Volume, 1000.0),
+    ("tsp", Dimension::Volume, 5.0),
+    ("tl", Dimension::Volume, 5.0),
+    ("tbsp", Dimension::Volume, 15.0),
+    ("el", Dimension::Volume, 15.0),
+    ("cup", Dimension::Volume, 240.0),
+    ("cups", Dimension::Volume, 240.0),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Dimension {
+    Mass,
+    Volume,
+}
+
+/// Contents of the prices file:
+///
+/// ```toml
+/// currency = "EUR"
+///
+/// [prices]
+/// flour = { price = 1.19, per = "1 kg" }
+/// butter = { price = 2.49, per = "250 g" }
+/// eggs = { price = 0.35 }
+/// ```
+#[derive(Deserialize)]
+struct PricesFile {
+    #[serde(default)]
+    currency: String,
+    #[serde(default)]
+    prices: BTreeMap<String, PriceEntry>,
+}
+
+#[derive(Deserialize)]
+struct PriceEntry {
+    price: f64,
+    /// Quantity the price is for, a single piece without it
+    per: Option<String>,
+}
+
+#[derive(Debug)]
+struct Price {
+    price: f64,
+    amount: f64,
+    unit: Option<String>,
+}
+
+/// Prices of ingredients, by their identifier (see [`Synonyms::id`]).
+#[derive(Debug)]
+pub struct Prices {
+    pub currency: String,
+    prices: BTreeMap<String, Price>,
+}
+
+/// Estimated cost of a recipe, amounts are rounded to cents.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Cost {
+    pub currency: String,
+    /// Cost of all priced ingredients
+    pub total: f64,
+    /// Total cost divided by the yield, unless the recipe yields a quantity
+    /// of a measurement unit (e.g. "1 l")
+    pub per_serving: Option<f64>,
+    /// Ingredients without a price or with quantities that can not be
+    /// converted to the unit of their price, as written in the recipe
+    pub unpriced: Vec<String>,
+}
+
+/// A recipe with its estimated cost, serialized as the recipe with an
+/// additional `cost` field (`null` without prices).
+#[derive(Debug, Serialize)]
+pub struct CostedRecipe {
+    #[serde(flatten)]
+    pub recipe: Recipe,
+    pub cost: Option<Cost>,
+}
+
+impl Deref for CostedRecipe {
+    type Target = Recipe;
+
+    fn deref(&self) -> &Recipe {
+        &self.recipe
+    }
+}
+
+impl Prices {
+    /// Loads the prices file of `dir`, there are no prices without it.
+    ///
+    /// Ingredient names are identified by their `synonyms`.
+    pub fn load(dir: &Path, synonyms: &Synonyms) -> Result<Option<Self>> {
+        let file: PricesFile = match fs::read_to_string(dir.join(PRICES_FILE_NAME)) {
+            Ok(data) => toml::from_str(&data).map_err(Error::InvalidPricesFileFormat)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+        let prices = file
+            .prices
+            .into_iter()
+            .map(|(name, entry)| {
+                let (amount, unit) = match entry.per {
+                    Some(per) => {
+                        let quantity = Quantity::parse_from_str(&per)
+                            .map_err(|error| Error::InvalidPrice(name.clone(), error))?;
+                        (to_f64(&quantity.value), quantity.unit)
+                    }
+                    None => (1.0, None),
+                };
+                let price = Price {
+                    price: entry.price,
+                    amount,
+                    unit,
+                };
+                Ok((synonyms.id(&name), price))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Some(Self {
+            currency: file.currency,
+            prices,
+        }))
+    }
+
+    /// Estimates the cost of `recipe` from the quantities of its ingredients.
+    ///
+    /// Quantities of mass and volume units are converted to the unit of the
+    /// price, other units must be the same. Ingredients without a quantity
+    /// are only priced if their price is per piece.
+    pub fn estimate(&self, recipe: &Recipe, synonyms: &Synonyms) -> Cost {
+        let mut total = 0.0;
+        let mut unpriced = Vec::new();
+        let mut seen = HashSet::new();
+        for ingredient in recipe.ingredients.iter() {
+            let cost = self
+                .prices
+                .get(&synonyms.id(&ingredient.name))
+                .and_then(|price| {
+                    let amount = match &ingredient.quantity {
+                        Some(quantity) => convert(
+                            to_f64(&quantity.value),
+                            quantity.unit.as_deref(),
+                            price.unit.as_deref(),
+                        )?,
+                        None if price.unit.is_none() => 1.0,
+                        None => return None,
+                    };
+                    Some(amount / price.amount * price.price)
+                });
+            match cost {
+                Some(cost) => total += cost,
+                None if seen.insert(normalize(&ingredient.name)) => {
+                    unpriced.push(ingredient.name.clone());
+                }
+                None => {}
+            }
+        }
+        let yields = &recipe.metadata.yields;
+        let per_serving = (yields.value > 0
+            && yields
+                .unit
+                .as_deref()
+                .is_none_or(|unit| unit_of(unit).is_none()))
+        .then(|| round(total / f64::from(yields.value)));
+        Cost {
+            currency: self.currency.clone(),
+            total: round(total),
+            per_serving,
+            unpriced,
+        }
+    }
+}
+
+fn to_f64(value: &QuantityValue) -> f64 {
+    let (numer, denom) = value.ratio();
+    numer as f64 / denom as f64
+}
+
+fn unit_of(name: &str) -> Option<(Dimension, f64)> {
+    let name = normalize(name);
+    UNITS
+        .iter()
+        .find(|(unit, _, _)| *unit == name)
+        .map(|&(_, dimension, factor)| (dimension, factor))
+}
+
+/// Converts `value` from the unit `from` to the unit `to`, if they are the
+/// same or of the same dimension.
+fn convert(value: f64, from: Option<&str>, to: Option<&str>) -> Option<f64> {
+    if from.map(normalize) == to.map(normalize) {
+        return Some(value);
+    }
+    let (from, to) = (unit_of(from?)?, unit_of(to?)?);
+    (from.0 == to.0).then(|| value * from.1 / to.1)
+}
+
+fn round(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn recipe(yields: &str, ingredients: &[&str]) -> Recipe {
+        let ingredients: String = ingredients
+            .iter()
+            .map(|ingredient| format!("- {ingredient}\n"))
+            .collect();
+        let text =
+            format!("Title\n\nYield: {yields}\n\nIngredients\n{ingredients}\nInstructions\n- none");
+        Recipe::parse_from(Cursor::new(text)).unwrap()
+    }
+
+    #[test]
+    fn test_convert() {
+        assert_eq!(convert(2.0, None, None), Some(2.0));
+        assert_eq!(convert(2.0, Some("Pinch"), Some("pinch")), Some(2.0));
+        assert_eq!(convert(500.0, Some("g"), Some("kg")), Some(0.5));
+        assert_eq!(convert(2.0, Some("tbsp"), Some("ml")), Some(30.0));
+        assert_eq!(convert(2.0, Some("g"), Some("ml")), None);
+        assert_eq!(convert(2.0, None, Some("g")), None);
+    }
+
+    #[test]
+    fn test_estimate() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let synonyms = Synonyms::new([("egg".to_string(), vec!["Eier".to_string()])]);
+        assert!(Prices::load(temp_dir.path(), &synonyms)?.is_none());
+        fs::write(
+            temp_dir.path().join(PRICES_FILE_NAME),
+            concat!(
+                "currency = \"EUR\"\n",
+                "[prices]\n",
+                "flour = { price = 1.2, per = \"1 kg\" }\n",
+                "milk = { price = 1, per = \"1 l\" }\n",
+                "eggs = { price = 0.3 }\n",
+                "butter = { price = 2.5, per = \"250 g\" }\n",
+            ),
+        )?;
+        let prices = Prices::load(temp_dir.path(), &synonyms)?.unwrap();
+        let cost = prices.estimate(
+            &recipe(
+                "2",
+                &[
+                    "Flour: 500 g",
+                    "milk: 1/2 l",
+                    "Eier: 2",
+                    "butter: 1 pinch",
+                    "sugar: 50 g",
+                    "salt",
+                    "Salt: 1 pinch",
+                ],
+            ),
+            &synonyms,
+        );
+        assert_eq!(
+            cost,
+            Cost {
+                currency: "EUR".into(),
+                total: 1.7,
+                per_serving: Some(0.85),
+                unpriced: vec!["butter".into(), "sugar".into(), "salt".into()],
+            }
+        );
+        let cost = prices.estimate(&recipe("1 l", &["milk: 1 l", "eggs"]), &synonyms);
+        assert_eq!((cost.total, cost.per_serving), (1.3, None));
+        let cost = prices.estimate(&recipe("12 cookies", &["flour: 600 g"]), &synonyms);
+        assert_eq!(cost.per_serving, Some(0.06));
+
+        fs::write(
+            temp_dir.path().join(PRICES_FILE_NAME),
+            "[prices]\nflour = { price = 1, per = \"kg\" }",
+        )?;
+        assert!(matches!(
+            Prices::load(temp_dir.path(), &synonyms),
+            Err(Error::InvalidPrice(..))
+        ));
+        Ok(())
+    }
+}
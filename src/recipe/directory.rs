@@ -1,4 +1,5 @@
 use std::{
+    collections::BTreeMap,
     ffi::{OsStr, OsString},
     fs,
     io::{self, Write},
@@ -10,10 +11,16 @@ use crate::{
     util::{append_os_file_ext, sanitize_file_name},
 };
 
-use super::Recipe;
+use super::{Recipe, redirects::Redirects};
 
 pub const RECIPE_FILE_EXT: &str = "recipe";
 
+/// Subdirectory of a recipe directory with additional images (gallery).
+pub const GALLERY_DIR: &str = "images";
+
+/// Optional file within `GALLERY_DIR` mapping image file names to captions.
+pub const CAPTIONS_FILE_NAME: &str = "captions.toml";
+
 #[derive(Debug)]
 pub struct Directory {
     parent: PathBuf,
@@ -53,13 +60,24 @@ impl Directory {
         &self.name
     }
 
+    /// Copies the image at `path` into the gallery (keeping its file name).
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `Path::file_name` returns `None`
+    pub fn add_gallery_image(&self, path: &Path, file_exts: &[&OsStr]) -> Result<()> {
+        check_image_file_ext(path, file_exts)?;
+        let gallery_path = self.gallery_path();
+        fs::create_dir_all(&gallery_path)?;
+        fs::copy(
+            path,
+            gallery_path.join(path.file_name().expect("invalid image file name")),
+        )?;
+        Ok(())
+    }
+
     pub fn copy_image_from(&self, path: &Path, file_exts: &[&OsStr]) -> Result<()> {
-        let ext = path
-            .extension()
-            .ok_or_else(|| Error::MissingImageFileExt(path.into()))?;
-        if !file_exts.contains(&ext) {
-            return Err(Error::InvalidImageFileExt(ext.into()));
-        }
+        let ext = check_image_file_ext(path, file_exts)?;
         fs::copy(path, self.file_path(ext))?;
         Ok(())
     }
@@ -68,6 +86,37 @@ impl Directory {
         fs::remove_dir_all(self.path())
     }
 
+    /// Returns the file names of all gallery images, sorted.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `Path::file_name` returns `None`
+    pub fn gallery_file_names(&self, file_exts: &[OsString]) -> io::Result<Vec<OsString>> {
+        let entries = match fs::read_dir(self.gallery_path()) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => return Err(error),
+        };
+        let mut file_names = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            if entry.file_type()?.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| file_exts.iter().any(|ext| ext == extension))
+            {
+                file_names.push(path.file_name().expect("invalid image file name").into());
+            }
+        }
+        file_names.sort();
+        Ok(file_names)
+    }
+
+    pub fn gallery_path(&self) -> PathBuf {
+        self.path().join(GALLERY_DIR)
+    }
+
     /// # Panics
     ///
     /// Will panic if `Path::file_name` returns `None`
@@ -98,6 +147,15 @@ impl Directory {
         })
     }
 
+    /// Loads the captions of the gallery images (by file name).
+    pub fn load_captions(&self) -> Result<BTreeMap<String, String>> {
+        match fs::read_to_string(self.gallery_path().join(CAPTIONS_FILE_NAME)) {
+            Ok(data) => toml::from_str(&data).map_err(Error::InvalidCaptionsFileFormat),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
     pub fn path(&self) -> PathBuf {
         self.parent.join(&self.name)
     }
@@ -106,6 +164,10 @@ impl Directory {
         self.file_path(OsStr::new(RECIPE_FILE_EXT)).into()
     }
 
+    pub fn remove_gallery_image(&self, file_name: &OsStr) -> io::Result<()> {
+        fs::remove_file(self.gallery_path().join(file_name))
+    }
+
     pub fn store(&mut self, recipe: &Recipe) -> io::Result<()> {
         if let Some(name) = create(&self.parent, &self.name)? {
             self.name = name;
@@ -151,16 +213,20 @@ impl Directory {
     }
 
     fn is_image_path(&self, path: &Path, file_exts: &[OsString]) -> bool {
-        path.file_stem().map_or(false, |stem| stem == self.name)
+        path.file_stem().is_some_and(|stem| stem == self.name)
             && matches!(
                 path.extension(),
                 Some(extension) if file_exts.iter().any(|ext|ext == extension)
             )
     }
 
+    /// The renaming is recorded in the redirects file of the parent directory.
     fn rename(&mut self, name: OsString) -> Result<()> {
         let path = self.parent.join(&name);
         fs::rename(self.path(), &path)?;
+        let mut redirects = Redirects::load(&self.parent)?;
+        redirects.insert(self.name.to_string_lossy(), name.to_string_lossy());
+        redirects.store(&self.parent)?;
         self.name = name;
         let mut files = Vec::new();
         for entry in fs::read_dir(&path)? {
@@ -180,6 +246,16 @@ impl Directory {
     }
 }
 
+fn check_image_file_ext<'a>(path: &'a Path, file_exts: &[&OsStr]) -> Result<&'a OsStr> {
+    let ext = path
+        .extension()
+        .ok_or_else(|| Error::MissingImageFileExt(path.into()))?;
+    if !file_exts.contains(&ext) {
+        return Err(Error::InvalidImageFileExt(ext.into()));
+    }
+    Ok(ext)
+}
+
 fn create(parent: &Path, name: &OsStr) -> io::Result<Option<OsString>> {
     match fs::create_dir(parent.join(name)) {
         Ok(()) => Ok(None),
@@ -208,7 +284,7 @@ fn find_available_name(
         name.push(" (");
         name.push(i.to_string());
         name.push(")");
-        if !parent.join(&name).exists() || current_name.map_or(false, |n| n == name) {
+        if !parent.join(&name).exists() || current_name.is_some_and(|n| n == name) {
             return Ok(name);
         }
         i += 1;
@@ -254,6 +330,8 @@ mod tests {
         fs::create_dir_all(&new_recipe_path_1)?;
         fs::File::create(recipe_path.join("recipe.recipe"))?;
         fs::File::create(recipe_path.join("recipe.jpg"))?;
+        fs::create_dir(recipe_path.join(GALLERY_DIR))?;
+        fs::File::create(recipe_path.join(GALLERY_DIR).join("step.jpg"))?;
 
         // update directory
         let mut directory = Directory {
@@ -268,6 +346,9 @@ mod tests {
         assert!(recipe_path.exists());
         assert!(recipe_path.join("new recipe (2).recipe").exists());
         assert!(recipe_path.join("new recipe (2).jpg").exists());
+        assert_eq!(directory.gallery_file_names(&["jpg".into()])?, ["step.jpg"]);
+        let redirects = Redirects::load(temp_path)?;
+        assert_eq!(redirects.resolve("recipe"), Some("new recipe (2)"));
 
         Ok(())
     }
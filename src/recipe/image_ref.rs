@@ -0,0 +1,47 @@
+//! References to gallery images, e.g. `@image(2)` or `@image(step.jpg)`, in
+//! instructions.
+
+use std::ops::Range;
+
+pub const IMAGE_REF_START: &str = "@image(";
+
+/// Returns the byte range and the argument (number or file name) of each
+/// image reference in `text`.
+pub fn find_image_refs(text: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let start = offset + text[offset..].find(IMAGE_REF_START)?;
+        let arg_start = start + IMAGE_REF_START.len();
+        let end = arg_start + text[arg_start..].find(')')?;
+        offset = end + 1;
+        Some((start..offset, text[arg_start..end].trim()))
+    })
+}
+
+/// Removes all image references from `text`.
+pub fn remove_image_refs(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut start = 0;
+    for (range, _) in find_image_refs(text) {
+        stripped.push_str(text[start..range.start].trim_end());
+        start = range.end;
+    }
+    stripped.push_str(&text[start..]);
+    stripped.trim().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_image_refs() {
+        let text = "Fold the dough @image( 2 ) and bake it @image(bake.jpg). @image(";
+        let refs: Vec<_> = find_image_refs(text).collect();
+        assert_eq!(refs, [(15..26, "2"), (39..55, "bake.jpg")]);
+        assert_eq!(
+            remove_image_refs(text),
+            "Fold the dough and bake it. @image("
+        );
+    }
+}
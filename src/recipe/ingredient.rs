@@ -71,7 +71,7 @@ impl Fraction {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 #[serde(rename_all = "snake_case")]
 pub enum QuantityValue {
     Integer(Integer),
@@ -96,6 +96,65 @@ impl QuantityValue {
         }
     }
 
+    /// Returns the value as numerator and denominator.
+    pub fn ratio(&self) -> (u64, u64) {
+        match *self {
+            Self::Integer(Integer(value)) => (value.into(), 1),
+            Self::Decimal(Decimal { int, frac }) => {
+                let denom = 10u64.pow(frac.to_string().len() as u32);
+                (u64::from(int) * denom + u64::from(frac), denom)
+            }
+            Self::Fraction(Fraction { numer, denom }) => (numer.into(), denom.into()),
+        }
+    }
+
+    /// Returns the value multiplied by `numer / denom`, as an integer or
+    /// (small) fraction if possible, otherwise as decimal with one
+    /// fractional digit.
+    pub fn scale(&self, numer: u64, denom: u64) -> Self {
+        let (value_numer, value_denom) = self.ratio();
+        let (numer, denom) = reduce(
+            u128::from(value_numer) * u128::from(numer),
+            u128::from(value_denom) * u128::from(denom),
+        );
+        if denom == 1
+            && let Ok(value) = numer.try_into()
+        {
+            return Self::Integer(Integer(value));
+        }
+        // fractions of small values only (e.g. `3/2`, but not `100/3`)
+        if [2, 3, 4, 8].contains(&denom)
+            && numer < 10 * denom
+            && let (Ok(numer), Ok(denom)) = (numer.try_into(), denom.try_into())
+        {
+            return Self::Fraction(Fraction { numer, denom });
+        }
+        // rounded to tenths, but not to zero
+        let tenths = ((numer * 10 + denom / 2) / denom).max(1);
+        match (u16::try_from(tenths / 10), tenths % 10) {
+            (Ok(int), 0) => Self::Integer(Integer(int.into())),
+            (Ok(int), frac) => Self::Decimal(Decimal {
+                int,
+                frac: frac as u16,
+            }),
+            (Err(_), _) => Self::Integer(Integer(u32::MAX)),
+        }
+    }
+
+    /// Returns the sum of both values, formatted like [`Self::scale`].
+    pub fn sum(&self, other: &Self) -> Self {
+        let (a_numer, a_denom) = self.ratio();
+        let (b_numer, b_denom) = other.ratio();
+        let (numer, denom) = reduce(
+            u128::from(a_numer) * u128::from(b_denom) + u128::from(b_numer) * u128::from(a_denom),
+            u128::from(a_denom) * u128::from(b_denom),
+        );
+        Self::Integer(Integer(1)).scale(
+            numer.try_into().unwrap_or(u64::MAX),
+            denom.try_into().unwrap_or(u64::MAX),
+        )
+    }
+
     fn parse_mixed_number(
         Integer(int): Integer,
         value: &str,
@@ -113,7 +172,19 @@ impl QuantityValue {
     }
 }
 
-#[derive(Debug, Serialize)]
+/// Reduces a fraction to lowest terms.
+pub(crate) fn reduce(numer: u128, denom: u128) -> (u128, u128) {
+    let (mut a, mut b) = (numer, denom);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    numer
+        .checked_div(a)
+        .zip(denom.checked_div(a))
+        .unwrap_or((0, 1))
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct Quantity {
     pub value: QuantityValue,
     pub unit: Option<String>,
@@ -170,7 +241,7 @@ impl ParseFromStr for Quantity {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 pub struct Ingredient {
     pub name: String,
     pub kind: Option<String>,
@@ -273,6 +344,35 @@ mod tests {
         assert_eq!(quantity.note, Some("a note".into()));
     }
 
+    #[test]
+    fn test_scale_quantity_value() {
+        let scale = |value: &str, numer, denom| {
+            let (value, _) = QuantityValue::parse_from_str(value).unwrap();
+            value.scale(numer, denom).to_string()
+        };
+        assert_eq!(scale("2", 3, 1), "6");
+        assert_eq!(scale("3", 1, 2), "3/2");
+        assert_eq!(scale("1/2", 1, 2), "1/4");
+        assert_eq!(scale("1/3", 3, 1), "1");
+        assert_eq!(scale("0.5", 3, 1), "3/2");
+        assert_eq!(scale("0.25", 1, 5), "0.1");
+        assert_eq!(scale("1", 1, 100), "0.1");
+        assert_eq!(scale("10", 1, 3), "10/3");
+        assert_eq!(scale("100", 1, 3), "33.3");
+    }
+
+    #[test]
+    fn test_sum_quantity_values() {
+        let sum = |a: &str, b: &str| {
+            let (a, _) = QuantityValue::parse_from_str(a).unwrap();
+            let (b, _) = QuantityValue::parse_from_str(b).unwrap();
+            a.sum(&b).to_string()
+        };
+        assert_eq!(sum("30", "10"), "40");
+        assert_eq!(sum("1/2", "1 1/4"), "7/4");
+        assert_eq!(sum("0.5", "0.2"), "0.7");
+    }
+
     #[test]
     fn test_display_ingredient() {
         let quantity = Quantity {
@@ -57,6 +57,16 @@ impl<D> List<D> {
                 .fold(0, |acc, section| acc + section.items.len()),
         }
     }
+
+    /// Iterates over the items of all sections.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = &D> + '_> {
+        match self {
+            Self::Basic(items) => Box::new(items.iter()),
+            Self::Sectioned(sections) => {
+                Box::new(sections.iter().flat_map(|section| &section.items))
+            }
+        }
+    }
 }
 
 impl<D: Serialize> Serialize for List<D> {
@@ -140,7 +150,7 @@ impl<D: ParseFromStr> TryFrom<Vec<String>> for List<D> {
     type Error = ParseError;
 
     fn try_from(lines: Vec<String>) -> Result<Self, Self::Error> {
-        if lines.first().map_or(true, |line| line.starts_with("- ")) {
+        if lines.first().is_none_or(|line| line.starts_with("- ")) {
             Ok(Self::Basic(Self::parse_basic(&lines)?))
         } else {
             Ok(Self::Sectioned(Self::parse_sectioned(&lines)?))
@@ -166,6 +176,7 @@ fn strip_prefix(line: &str) -> ParseResult<&str> {
 }
 
 #[cfg(test)]
+#[allow(clippy::get_first)]
 mod tests {
 
     use super::*;
@@ -220,6 +231,10 @@ mod tests {
         };
         let list = List::Sectioned(vec![section1, section2]);
         assert_eq!(list.count(), 3);
+        assert_eq!(
+            list.iter().collect::<Vec<_>>(),
+            [&"item 1.1", &"item 1.2", &"item 2.1"]
+        );
     }
 
     #[test]
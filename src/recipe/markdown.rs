@@ -0,0 +1,313 @@
+//! Inline Markdown subset of instructions and notes: emphasis (`*text*` or
+//! `_text_`), strong emphasis (`**text**` or `__text__`), code (`` `code` ``)
+//! and links (`[text](url)`). A backslash escapes any of `` \*_`[]() ``.
+//!
+//! References to other recipes (see [`super::reference`]) are parsed as
+//! well. Recipes keep the source text, it is only parsed for rendering.
+
+use super::reference::{REFERENCE_START, find_references};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Inline {
+    Text(String),
+    Emphasis(Vec<Inline>),
+    Strong(Vec<Inline>),
+    Code(String),
+    Link {
+        content: Vec<Inline>,
+        url: String,
+    },
+    /// Title of a referenced recipe
+    Reference(String),
+}
+
+impl Inline {
+    /// Returns the text without any formatting (and without link URLs).
+    pub fn plain_text(inlines: &[Self]) -> String {
+        let mut text = String::new();
+        for inline in inlines {
+            match inline {
+                Self::Text(part) | Self::Code(part) | Self::Reference(part) => {
+                    text.push_str(part);
+                }
+                Self::Emphasis(content) | Self::Strong(content) | Self::Link { content, .. } => {
+                    text.push_str(&Self::plain_text(content));
+                }
+            }
+        }
+        text
+    }
+}
+
+const ESCAPABLE: &[char] = &['\\', '*', '_', '`', '[', ']', '(', ')'];
+
+/// Parses the inline Markdown of `text`. Delimiters without a match are
+/// kept as text.
+pub fn parse(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut literal = String::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        if c == '\\'
+            && let Some(next) = text[i + 1..].chars().next()
+            && ESCAPABLE.contains(&next)
+        {
+            literal.push(next);
+            i += 1 + next.len_utf8();
+            continue;
+        }
+        let parsed = match c {
+            '`' => parse_code(text, i),
+            '[' => parse_link(text, i),
+            '*' | '_' => parse_emphasis(text, i),
+            '@' => parse_reference(text, i),
+            _ => None,
+        };
+        if let Some((inline, end)) = parsed {
+            if !literal.is_empty() {
+                inlines.push(Inline::Text(std::mem::take(&mut literal)));
+            }
+            inlines.push(inline);
+            i = end;
+        } else {
+            literal.push(c);
+            i += c.len_utf8();
+        }
+    }
+    if !literal.is_empty() {
+        inlines.push(Inline::Text(literal));
+    }
+    inlines
+}
+
+/// Parses the references of `text` only, the rest is kept as text.
+pub fn parse_references(text: &str) -> Vec<Inline> {
+    let mut inlines = Vec::new();
+    let mut start = 0;
+    for (range, title) in find_references(text) {
+        if range.start > start {
+            inlines.push(Inline::Text(text[start..range.start].into()));
+        }
+        inlines.push(Inline::Reference(title.into()));
+        start = range.end;
+    }
+    if start < text.len() {
+        inlines.push(Inline::Text(text[start..].into()));
+    }
+    inlines
+}
+
+fn parse_reference(text: &str, start: usize) -> Option<(Inline, usize)> {
+    if !text[start..].starts_with(REFERENCE_START) {
+        return None;
+    }
+    let (range, title) = find_references(&text[start..]).next()?;
+    Some((Inline::Reference(title.into()), start + range.end))
+}
+
+/// Parses a code span starting at `start`, returns it with its end.
+fn parse_code(text: &str, start: usize) -> Option<(Inline, usize)> {
+    let end = start + 1 + text[start + 1..].find('`')?;
+    let code = &text[start + 1..end];
+    (!code.is_empty()).then(|| (Inline::Code(code.into()), end + 1))
+}
+
+fn parse_link(text: &str, start: usize) -> Option<(Inline, usize)> {
+    let mut depth = 0;
+    let close = find(text, start + 1, |i| {
+        match text.as_bytes()[i] {
+            b'[' => depth += 1,
+            b']' if depth == 0 => return true,
+            b']' => depth -= 1,
+            _ => {}
+        }
+        false
+    })?;
+    let url_start = close + 1;
+    if !text[url_start..].starts_with('(') {
+        return None;
+    }
+    // URLs may contain balanced parentheses
+    let mut depth = 0;
+    let url_end = url_start
+        + 1
+        + text[url_start + 1..].find(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' if depth == 0 => return true,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            false
+        })?;
+    let url = &text[url_start + 1..url_end];
+    let content = &text[start + 1..close];
+    if content.is_empty() || url.is_empty() || url.contains(char::is_whitespace) {
+        return None;
+    }
+    let link = Inline::Link {
+        content: parse(content),
+        url: url.into(),
+    };
+    Some((link, url_end + 1))
+}
+
+fn parse_emphasis(text: &str, start: usize) -> Option<(Inline, usize)> {
+    let delimiter = text.as_bytes()[start];
+    let is_strong = text.as_bytes().get(start + 1) == Some(&delimiter);
+    let length = if is_strong { 2 } else { 1 };
+    let content_start = start + length;
+    let is_underscore = delimiter == b'_';
+    // an opening delimiter is followed by text, underscores do not open within words
+    let is_followed_by_text =
+        !text[content_start..].starts_with(char::is_whitespace) && content_start < text.len();
+    let is_within_word = text[..start].ends_with(char::is_alphanumeric);
+    if !is_followed_by_text || is_underscore && is_within_word {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let end = find(text, content_start, |i| {
+        let run = bytes[i..].iter().take_while(|&&b| b == delimiter).count();
+        let is_closing = i > content_start
+            && run >= length
+            && !text[..i].ends_with(char::is_whitespace)
+            && !(is_underscore && text[i + length..].starts_with(|c: char| c.is_alphanumeric()));
+        if is_strong {
+            // the closing delimiter is the end of a run (`***text***`)
+            is_closing && run == length
+        } else {
+            // single delimiters do not close within runs of strong delimiters
+            is_closing && run == 1 && bytes[i - 1] != delimiter
+        }
+    })?;
+    let content = parse(&text[content_start..end]);
+    let inline = if is_strong {
+        Inline::Strong(content)
+    } else {
+        Inline::Emphasis(content)
+    };
+    Some((inline, end + length))
+}
+
+/// Returns the first byte position from `start` matching `predicate`,
+/// skipping escaped characters and code spans.
+fn find(text: &str, start: usize, mut predicate: impl FnMut(usize) -> bool) -> Option<usize> {
+    let mut i = start;
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '\\' if text[i + 1..].starts_with(ESCAPABLE) => {
+                i += 1 + text[i + 1..].chars().next()?.len_utf8();
+                continue;
+            }
+            '`' if let Some((_, end)) = parse_code(text, i) => {
+                i = end;
+                continue;
+            }
+            _ if predicate(i) => return Some(i),
+            _ => i += c.len_utf8(),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Inline::*, *};
+
+    fn text(text: &str) -> Inline {
+        Text(text.into())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("Bake at `180 °C`, **do not** open the *oven*."),
+            [
+                text("Bake at "),
+                Code("180 °C".into()),
+                text(", "),
+                Strong(vec![text("do not")]),
+                text(" open the "),
+                Emphasis(vec![text("oven")]),
+                text("."),
+            ]
+        );
+        assert_eq!(
+            parse("See [the *video*](https://example.com/a_(b)) __now__"),
+            [
+                text("See "),
+                Link {
+                    content: vec![text("the "), Emphasis(vec![text("video")])],
+                    url: "https://example.com/a_(b)".into(),
+                },
+                text(" "),
+                Strong(vec![text("now")]),
+            ]
+        );
+        assert_eq!(
+            parse("***both*** and *a **b** c*"),
+            [
+                Strong(vec![Emphasis(vec![text("both")])]),
+                text(" and "),
+                Emphasis(vec![text("a "), Strong(vec![text("b")]), text(" c")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_literal() {
+        for source in [
+            "2 * 3 * 4",
+            "snake_case_name",
+            "**",
+            "a ** b",
+            "`",
+            "[text] (url)",
+            "[text](an url)",
+            "*unclosed",
+            "<b>html</b>",
+        ] {
+            assert_eq!(parse(source), [text(source)], "{source}");
+        }
+        assert_eq!(
+            parse(r"\*not emphasized\* \\ *`*`*"),
+            [
+                text(r"*not emphasized* \ "),
+                Emphasis(vec![Code("*".into())])
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_references() {
+        assert_eq!(
+            parse("Add the *warm* @[Béchamel] [sauce](url)"),
+            [
+                text("Add the "),
+                Emphasis(vec![text("warm")]),
+                text(" "),
+                Reference("Béchamel".into()),
+                text(" "),
+                Link {
+                    content: vec![text("sauce")],
+                    url: "url".into()
+                },
+            ]
+        );
+        assert_eq!(
+            parse_references("@[Ragù] and *@[Béchamel]*"),
+            [
+                Reference("Ragù".into()),
+                text(" and *"),
+                Reference("Béchamel".into()),
+                text("*"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plain_text() {
+        let inlines = parse("**Do not** stir, see [video](https://example.com)");
+        assert_eq!(Inline::plain_text(&inlines), "Do not stir, see video");
+    }
+}
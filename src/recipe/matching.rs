@@ -0,0 +1,164 @@
+//! Recipes matching a set of available ingredients or a pantry.
+
+use std::collections::HashSet;
+
+use super::{Recipe, ingredient::Ingredient, pantry::Pantry, synonyms::Synonyms};
+
+/// A recipe using some of the given ingredients.
+#[derive(Debug)]
+pub struct Match<'a> {
+    pub recipe: &'a Recipe,
+    /// Ingredients of the recipe among the given ingredients
+    pub used: Vec<&'a str>,
+    /// Other ingredients of the recipe
+    pub others: Vec<&'a str>,
+}
+
+/// A recipe with the ingredients missing from a pantry.
+#[derive(Debug)]
+pub struct Coverage<'a> {
+    pub recipe: &'a Recipe,
+    pub missing: Vec<&'a Ingredient>,
+}
+
+/// Returns the recipes using at least one of `ingredients`, those using the
+/// most of them first and, among these, those needing the fewest other
+/// ingredients.
+///
+/// Ingredient names match regardless of case and with their synonyms. Each
+/// ingredient of a recipe is only counted once.
+pub fn rank<'a, S: AsRef<str>>(
+    recipes: &'a [Recipe],
+    ingredients: &[S],
+    synonyms: &Synonyms,
+) -> Vec<Match<'a>> {
+    let ids: HashSet<_> = ingredients
+        .iter()
+        .map(|name| synonyms.id(name.as_ref()))
+        .collect();
+    let mut matches: Vec<_> = recipes
+        .iter()
+        .filter_map(|recipe| {
+            let mut seen = HashSet::new();
+            let (mut used, mut others) = (Vec::new(), Vec::new());
+            for ingredient in recipe.ingredients.iter() {
+                let id = synonyms.id(&ingredient.name);
+                if !seen.insert(id.clone()) {
+                    continue;
+                }
+                if ids.contains(&id) {
+                    used.push(ingredient.name.as_str());
+                } else {
+                    others.push(ingredient.name.as_str());
+                }
+            }
+            (!used.is_empty()).then_some(Match {
+                recipe,
+                used,
+                others,
+            })
+        })
+        .collect();
+    matches.sort_by(|a, b| {
+        b.used
+            .len()
+            .cmp(&a.used.len())
+            .then(a.others.len().cmp(&b.others.len()))
+            .then(a.recipe.title.cmp(&b.recipe.title))
+    });
+    matches
+}
+
+/// Returns the recipes missing at most `max_missing` ingredients from
+/// `pantry` (see [`Pantry::covers`]), those missing the fewest first.
+pub fn can_make<'a>(
+    recipes: &'a [Recipe],
+    pantry: &Pantry,
+    max_missing: usize,
+    synonyms: &Synonyms,
+) -> Vec<Coverage<'a>> {
+    let mut coverages: Vec<_> = recipes
+        .iter()
+        .map(|recipe| Coverage {
+            recipe,
+            missing: recipe
+                .ingredients
+                .iter()
+                .filter(|ingredient| !pantry.covers(ingredient, synonyms))
+                .collect(),
+        })
+        .filter(|coverage| coverage.missing.len() <= max_missing)
+        .collect();
+    coverages.sort_by(|a, b| {
+        a.missing
+            .len()
+            .cmp(&b.missing.len())
+            .then(a.recipe.title.cmp(&b.recipe.title))
+    });
+    coverages
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::recipe::ParseFromStr;
+
+    use super::*;
+
+    fn recipe(title: &str, ingredients: &[&str]) -> Recipe {
+        let ingredients: String = ingredients
+            .iter()
+            .map(|name| format!("- {name}\n"))
+            .collect();
+        let text =
+            format!("{title}\n\nYield: 1\n\nIngredients\n{ingredients}\nInstructions\n- none");
+        Recipe::parse_from(Cursor::new(text)).unwrap()
+    }
+
+    #[test]
+    fn test_rank() {
+        let recipes = [
+            recipe("Salad", &["tomatoes", "cucumber", "oil", "vinegar"]),
+            recipe("Soup", &["Tomatoes", "onion", "tomato"]),
+            recipe("Sauce", &["tomato", "basil"]),
+            recipe("Cake", &["flour", "sugar"]),
+            recipe("Bread", &["flour", "water", "salt"]),
+        ];
+        let synonyms = Synonyms::new([("tomato".to_string(), vec!["tomatoes".to_string()])]);
+        let matches = rank(&recipes, &["Tomato", "basil", "flour"], &synonyms);
+        let titles: Vec<_> = matches.iter().map(|m| m.recipe.title.as_str()).collect();
+        assert_eq!(titles, ["Sauce", "Cake", "Soup", "Bread", "Salad"]);
+        assert_eq!(matches[0].used, ["tomato", "basil"]);
+        assert!(matches[0].others.is_empty());
+        assert_eq!(matches[2].used, ["Tomatoes"]);
+        assert_eq!(matches[2].others, ["onion"]);
+        assert!(rank(&recipes, &["rice"], &synonyms).is_empty());
+    }
+
+    #[test]
+    fn test_can_make() {
+        let recipes = [
+            recipe("Sauce", &["tomatoes", "basil", "salt"]),
+            recipe("Bread", &["flour", "water", "salt"]),
+            recipe("Cake", &["flour", "sugar", "eggs"]),
+        ];
+        let synonyms = Synonyms::new([("tomato".to_string(), vec!["tomatoes".to_string()])]);
+        let mut pantry = Pantry::default();
+        pantry.staples = vec!["salt".into(), "water".into()];
+        for item in ["tomato", "flour", "basil"] {
+            pantry.add(Ingredient::parse_from_str(item).unwrap(), &synonyms);
+        }
+        let coverages = can_make(&recipes, &pantry, 1, &synonyms);
+        let titles: Vec<_> = coverages.iter().map(|c| c.recipe.title.as_str()).collect();
+        assert_eq!(titles, ["Bread", "Sauce"]);
+        assert!(coverages[0].missing.is_empty());
+        let coverages = can_make(&recipes, &pantry, 2, &synonyms);
+        let missing: Vec<_> = coverages[2]
+            .missing
+            .iter()
+            .map(|i| i.name.as_str())
+            .collect();
+        assert_eq!(missing, ["sugar", "eggs"]);
+    }
+}
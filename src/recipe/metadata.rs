@@ -1,11 +1,12 @@
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, time::SystemTime};
 
-use serde::Serialize;
+use serde::{Serialize, Serializer};
 
 use super::{
     ParseFromStr,
     errors::{ParseError, ParseResult},
 };
+use crate::util::date_to_time;
 
 #[derive(Debug, Serialize)]
 pub struct Yield {
@@ -96,6 +97,78 @@ impl ParseFromStr for Duration {
     }
 }
 
+/// A calendar date, written as `YYYY-MM-DD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Date {
+    pub year: u32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    /// Returns the start of the date (UTC).
+    pub fn to_system_time(self) -> SystemTime {
+        date_to_time(self.year, self.month, self.day)
+    }
+
+    fn days_in_month(year: u32, month: u32) -> u32 {
+        match month {
+            2 if year.is_multiple_of(4)
+                && (!year.is_multiple_of(100) || year.is_multiple_of(400)) =>
+            {
+                29
+            }
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl ParseFromStr for Date {
+    fn parse_from_str(s: &str) -> ParseResult<Self> {
+        let error = || {
+            format!(
+                "metadata value for key '{}' must be a date like '2024-03-01'",
+                Metadata::ADDED_KEY
+            )
+        };
+        let parts: Vec<_> = s.split('-').collect();
+        let [year, month, day] = parts[..] else {
+            return Err(error().into());
+        };
+        let number = |text: &str, len| {
+            (text.len() == len && text.bytes().all(|b| b.is_ascii_digit()))
+                .then(|| text.parse().ok())
+                .flatten()
+                .ok_or_else(error)
+        };
+        let date = Self {
+            year: number(year, 4)?,
+            month: number(month, 2)?,
+            day: number(day, 2)?,
+        };
+        if !(1..=12).contains(&date.month)
+            || !(1..=Self::days_in_month(date.year, date.month)).contains(&date.day)
+        {
+            return Err(error().into());
+        }
+        Ok(date)
+    }
+}
+
+impl Serialize for Date {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct Link {
     pub name: String,
@@ -154,12 +227,17 @@ pub struct Metadata {
     pub yields: Yield,
     pub source: Option<Source>,
     pub tags: Vec<String>,
+    pub slug: Option<String>,
+    /// Date the recipe was added to the collection
+    pub added: Option<Date>,
 }
 
 impl Metadata {
     const DURATION_KEY: &'static str = "Time";
     const YIELD_KEY: &'static str = "Yield";
     const TAGS_KEY: &'static str = "Tags";
+    const SLUG_KEY: &'static str = "Slug";
+    const ADDED_KEY: &'static str = "Added";
 }
 
 impl fmt::Display for Metadata {
@@ -174,6 +252,12 @@ impl fmt::Display for Metadata {
         if !self.tags.is_empty() {
             writeln!(f, "{}: {}", Self::TAGS_KEY, self.tags.join(", "))?;
         }
+        if let Some(slug) = &self.slug {
+            writeln!(f, "{}: {}", Self::SLUG_KEY, slug)?;
+        }
+        if let Some(added) = &self.added {
+            writeln!(f, "{}: {}", Self::ADDED_KEY, added)?;
+        }
         Ok(())
     }
 }
@@ -216,6 +300,12 @@ impl TryFrom<HashMap<String, String>> for Metadata {
         let tags = map.remove(Self::TAGS_KEY).map_or_else(Vec::new, |value| {
             value.split(", ").map(|s| s.trim().into()).collect()
         });
+        let slug = map.remove(Self::SLUG_KEY);
+        let added = map
+            .remove(Self::ADDED_KEY)
+            .as_deref()
+            .map(Date::parse_from_str)
+            .transpose()?;
         if let Some(key) = map.keys().next() {
             return Err(format!("unknown metadata key '{key}'").into());
         }
@@ -224,6 +314,8 @@ impl TryFrom<HashMap<String, String>> for Metadata {
             yields,
             source,
             tags,
+            slug,
+            added,
         };
         Ok(metadata)
     }
@@ -313,12 +405,31 @@ mod tests {
         assert_eq!(source.to_string(), "Book: name");
     }
 
+    #[test]
+    fn test_parse_date() {
+        let date = Date::parse_from_str("2024-02-29").unwrap();
+        assert_eq!((date.year, date.month, date.day), (2024, 2, 29));
+        assert_eq!(date.to_string(), "2024-02-29");
+        assert_eq!(serde_json::to_value(date).unwrap(), "2024-02-29");
+        for invalid in [
+            "2023-02-29",
+            "2024-13-01",
+            "2024-1-01",
+            "24-01-01",
+            "2024-01-01T00",
+        ] {
+            assert!(Date::parse_from_str(invalid).is_err());
+        }
+    }
+
     #[test]
     fn test_metadata() {
         let mut map = HashMap::new();
         map.insert("Yield".into(), "1  unit".into());
         map.insert("Link".into(), "name> > >url".into());
         map.insert("Tags".into(), "tag1 ,  tag2".into());
+        map.insert("Slug".into(), "slug".into());
+        map.insert("Added".into(), "2024-03-01".into());
         let metadata: Metadata = map.try_into().unwrap();
         assert_eq!(metadata.yields.value, 1);
         assert_eq!(metadata.yields.unit.as_deref(), Some("unit"));
@@ -326,9 +437,10 @@ mod tests {
             matches!(&metadata.source, Some(Source::Link(link)) if link.name =="name>" && link.url == ">url")
         );
         assert_eq!(&metadata.tags, &["tag1", "tag2"]);
+        assert_eq!(metadata.slug.as_deref(), Some("slug"));
         assert_eq!(
             metadata.to_string(),
-            "Yield: 1 unit\nLink: name> > >url\nTags: tag1, tag2\n"
+            "Yield: 1 unit\nLink: name> > >url\nTags: tag1, tag2\nSlug: slug\nAdded: 2024-03-01\n"
         );
     }
 
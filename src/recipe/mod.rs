@@ -1,11 +1,19 @@
+pub mod cost;
 pub mod directory;
 pub mod errors;
+pub mod image_ref;
 pub mod ingredient;
 pub mod list;
+pub mod markdown;
+pub mod matching;
 pub mod metadata;
+pub mod pantry;
 mod reader;
+pub mod redirects;
+pub mod reference;
+pub mod synonyms;
 
-use std::{fmt, io};
+use std::{collections::BTreeSet, fmt, io};
 
 use serde::Serialize;
 
@@ -13,12 +21,17 @@ use crate::error::Result;
 
 use self::{
     errors::{ParseError, ParseResult},
+    image_ref::remove_image_refs,
     ingredient::Ingredient,
     list::List,
+    markdown::Inline,
     metadata::Metadata,
     reader::Reader,
 };
 
+/// Maximum number of characters of `Recipe::summary`.
+const SUMMARY_LENGTH: usize = 200;
+
 pub trait ParseFromStr: Sized {
     fn parse_from_str(s: &str) -> ParseResult<Self>;
 }
@@ -33,6 +46,8 @@ impl ParseFromStr for String {
 pub struct Recipe {
     pub title: String,
     pub metadata: Metadata,
+    /// Paragraphs of free text, with lines separated by `\n`
+    pub description: Vec<String>,
     pub ingredients: List<Ingredient>,
     pub instructions: List<String>,
     pub notes: Vec<String>,
@@ -41,19 +56,32 @@ pub struct Recipe {
 impl Recipe {
     pub fn parse_from(reader: impl io::Read) -> Result<Self> {
         let mut reader = Reader::new(reader, true);
-        Ok(Self {
-            title: reader
-                .next_block()?
-                .ok_or_else(|| ParseError::from("missing title"))
-                .and_then(Self::parse_title)?,
-            metadata: reader
-                .next_block()?
-                .ok_or_else(|| ParseError::from("missing metadata"))
-                .and_then(Metadata::try_from)?,
-            ingredients: reader
+        let title = reader
+            .next_block()?
+            .ok_or_else(|| ParseError::from("missing title"))
+            .and_then(Self::parse_title)?;
+        let metadata = reader
+            .next_block()?
+            .ok_or_else(|| ParseError::from("missing metadata"))
+            .and_then(Metadata::try_from)?;
+        let mut description = Vec::new();
+        let ingredients = loop {
+            let lines = reader
                 .next_block()?
-                .ok_or_else(|| ParseError::from("missing ingredients"))
-                .and_then(Self::parse_ingredients)?,
+                .ok_or_else(|| ParseError::from("missing ingredients"))?;
+            match lines.first().map(String::as_str) {
+                Some("Ingredients") => break Self::parse_ingredients(lines)?,
+                Some("Instructions" | "Notes") => {
+                    return Err(ParseError::from("missing headline 'Ingredients'").into());
+                }
+                _ => description.push(lines.join("\n")),
+            }
+        };
+        Ok(Self {
+            title,
+            metadata,
+            description,
+            ingredients,
             instructions: reader
                 .next_block()?
                 .ok_or_else(|| ParseError::from("missing instructions"))
@@ -64,6 +92,47 @@ impl Recipe {
         })
     }
 
+    /// Returns a short plain text summary of the recipe (e.g. for link
+    /// previews): the first paragraph of the description, the first note or
+    /// else the first instruction, without image references and inline
+    /// Markdown formatting, with the titles of referenced recipes and
+    /// shortened to whole words.
+    pub fn summary(&self) -> Option<String> {
+        let text = self
+            .description
+            .first()
+            .or_else(|| self.notes.first())
+            .or_else(|| self.instructions.iter().next())?;
+        let text = Inline::plain_text(&markdown::parse(&remove_image_refs(text)));
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.chars().count() <= SUMMARY_LENGTH {
+            return (!text.is_empty()).then_some(text);
+        }
+        let mut summary = String::new();
+        for word in text.split(' ') {
+            if summary.chars().count() + word.chars().count() + 1 > SUMMARY_LENGTH - 1 {
+                break;
+            }
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(word);
+        }
+        summary.push('…');
+        Some(summary)
+    }
+
+    /// Returns the titles of all recipes referenced by ingredient names,
+    /// instructions and notes (see [`reference`]), sorted.
+    pub fn references(&self) -> BTreeSet<&str> {
+        let names = self.ingredients.iter().map(|ingredient| &ingredient.name);
+        names
+            .chain(self.instructions.iter())
+            .chain(&self.notes)
+            .flat_map(|text| reference::find_references(text).map(|(_, title)| title))
+            .collect()
+    }
+
     fn parse_title(mut lines: Vec<String>) -> ParseResult<String> {
         if lines.len() > 1 {
             return Err("missing empty line after title line".into());
@@ -76,7 +145,11 @@ impl Recipe {
             return Err("missing headline 'Ingredients'".into());
         }
         lines.remove(0);
-        lines.try_into()
+        let ingredients: List<Ingredient> = lines.try_into()?;
+        for ingredient in ingredients.iter() {
+            reference::validate(&ingredient.name)?;
+        }
+        Ok(ingredients)
     }
 
     fn parse_instructions(mut lines: Vec<String>) -> ParseResult<List<String>> {
@@ -84,7 +157,11 @@ impl Recipe {
             return Err("missing headline 'Instructions'".into());
         }
         lines.remove(0);
-        lines.try_into()
+        let instructions: List<String> = lines.try_into()?;
+        for instruction in instructions.iter() {
+            reference::validate(instruction)?;
+        }
+        Ok(instructions)
     }
 
     fn parse_notes(mut lines: Vec<String>) -> ParseResult<Vec<String>> {
@@ -92,7 +169,11 @@ impl Recipe {
             return Err("expected headline 'Notes'".into());
         }
         lines.remove(0);
-        List::<String>::parse_basic(&lines)
+        let notes: Vec<String> = List::<String>::parse_basic(&lines)?;
+        for note in &notes {
+            reference::validate(note)?;
+        }
+        Ok(notes)
     }
 }
 
@@ -101,6 +182,9 @@ impl fmt::Display for Recipe {
         let indentation = " ".repeat(2);
         writeln!(f, "{}\n", self.title)?;
         writeln!(f, "{}", self.metadata)?;
+        for paragraph in &self.description {
+            writeln!(f, "{paragraph}\n")?;
+        }
         writeln!(f, "Ingredients")?;
         self.ingredients.format(f, &indentation)?;
         writeln!(f, "\nInstructions")?;
@@ -114,6 +198,7 @@ impl fmt::Display for Recipe {
 }
 
 #[cfg(test)]
+#[allow(clippy::get_first)]
 mod tests {
 
     use super::{
@@ -131,6 +216,12 @@ mod tests {
         " Link: the name  >  the url\n",
         " Tags: tag 1 ,  tag 2\n",
         " \n",
+        " A  short \n",
+        " description \n",
+        " \n",
+        " \n",
+        " Ingredients are listed below. \n",
+        " \n",
         " Ingredients \n",
         " section 1 \n",
         " -  name 1 ,  kind 1 :  10  1 unit  ( note 1 ) \n",
@@ -156,6 +247,11 @@ mod tests {
         "Link: name > url\n",
         "Tags: tag1, tag2\n",
         "\n",
+        "first line\n",
+        "second line\n",
+        "\n",
+        "paragraph\n",
+        "\n",
         "Ingredients\n",
         "  section\n",
         "    - name\n",
@@ -186,7 +282,10 @@ mod tests {
                     url: "url".into(),
                 })),
                 tags: vec!["tag1".into(), "tag2".into()],
+                slug: None,
+                added: None,
             },
+            description: vec!["first line\nsecond line".into(), "paragraph".into()],
             ingredients: List::Sectioned(vec![Section::new(
                 "section".into(),
                 vec![Ingredient {
@@ -229,6 +328,12 @@ mod tests {
         }
         assert_eq!(metadata.tags, ["tag 1", "tag 2"]);
 
+        // description
+        assert_eq!(
+            recipe.description,
+            ["A  short\ndescription", "Ingredients are listed below."]
+        );
+
         // ingredients
         assert!(matches!(recipe.ingredients, List::Sectioned(_)));
         if let List::Sectioned(sections) = recipe.ingredients {
@@ -286,5 +391,61 @@ mod tests {
         assert!(Recipe::parse_from(reader).is_err());
         let reader = io::Cursor::new("title\n\nYield: 1\n\nIngredients\nNothing");
         assert!(Recipe::parse_from(reader).is_err());
+        let reader = io::Cursor::new("title\n\nYield: 1\n\nInstructions\n- nothing");
+        assert!(Recipe::parse_from(reader).is_err());
+    }
+
+    #[test]
+    fn test_display_inline_markdown() {
+        let text = concat!(
+            "title\n\nYield: 1\n\nIngredients\n  - name\n\n",
+            "Instructions\n  - Do **not** stir, see [video](https://example.com/a_(b))\n\n",
+            "Notes\n  - \\*literal\\* `180 °C`\n"
+        );
+        let recipe = Recipe::parse_from(io::Cursor::new(text)).unwrap();
+        assert_eq!(recipe.to_string(), text);
+    }
+
+    #[test]
+    fn test_references() {
+        let text = concat!(
+            "Lasagna\n\nYield: 4\n\nIngredients\n- @[Béchamel]: 500 ml\n- @[Ragù]\n- pasta\n\n",
+            "Instructions\n- Layer pasta, @[Ragù] and @[Béchamel]"
+        );
+        let recipe = Recipe::parse_from(io::Cursor::new(text)).unwrap();
+        assert_eq!(
+            recipe.references().into_iter().collect::<Vec<_>>(),
+            ["Béchamel", "Ragù"]
+        );
+        assert_eq!(recipe.to_string().matches("@[Béchamel]").count(), 2);
+        let text = "title\n\nYield: 1\n\nIngredients\n- @[Béchamel\n\nInstructions\n- none";
+        assert!(Recipe::parse_from(io::Cursor::new(text)).is_err());
+    }
+
+    #[test]
+    fn test_summary() {
+        let text = "title\n\nYield: 1\n\nIngredients\n- name\n\nInstructions\n- Mix  everything. @image(1)\n- Bake";
+        let mut recipe = Recipe::parse_from(io::Cursor::new(text)).unwrap();
+        assert_eq!(recipe.summary().as_deref(), Some("Mix everything."));
+        recipe.notes = vec!["word ".repeat(100)];
+        let summary = recipe.summary().unwrap();
+        assert!(summary.chars().count() <= SUMMARY_LENGTH);
+        assert!(summary.ends_with("word…"));
+        recipe.description = vec!["A  classic.\nFrom grandma.".into()];
+        assert_eq!(
+            recipe.summary().as_deref(),
+            Some("A classic. From grandma.")
+        );
+        recipe.description =
+            vec!["A **very** _quick_ `dal` from [India](https://example.com).".into()];
+        assert_eq!(
+            recipe.summary().as_deref(),
+            Some("A very quick dal from India.")
+        );
+        recipe.description = vec!["Serve with @[Béchamel] and salad.".into()];
+        assert_eq!(
+            recipe.summary().as_deref(),
+            Some("Serve with Béchamel and salad.")
+        );
     }
 }
@@ -0,0 +1,193 @@
+use std::{fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+use super::{
+    ParseFromStr,
+    ingredient::{Ingredient, Quantity},
+    synonyms::{Synonyms, normalize},
+};
+
+pub const PANTRY_FILE_NAME: &str = "pantry.toml";
+
+/// Contents of the pantry file, items are written like recipe ingredients:
+///
+/// ```toml
+/// staples = ["salt", "pepper"]
+/// items = ["flour: 1 kg", "tomatoes, canned: 2", "eggs"]
+/// ```
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default)]
+struct PantryFile {
+    staples: Vec<String>,
+    items: Vec<String>,
+}
+
+/// Ingredients on hand, with optional quantities.
+#[derive(Debug, Default)]
+pub struct Pantry {
+    /// Ingredients that are always available, e.g. salt and pepper
+    pub staples: Vec<String>,
+    items: Vec<Ingredient>,
+}
+
+impl Pantry {
+    /// Loads the pantry file of `dir`, the pantry is empty without it.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let file: PantryFile = match fs::read_to_string(dir.join(PANTRY_FILE_NAME)) {
+            Ok(data) => toml::from_str(&data).map_err(Error::InvalidPantryFileFormat)?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => PantryFile::default(),
+            Err(error) => return Err(error.into()),
+        };
+        let items = file
+            .items
+            .into_iter()
+            .map(|item| {
+                Ingredient::parse_from_str(&item)
+                    .map_err(|error| Error::InvalidPantryItem(item, error))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            staples: file.staples,
+            items,
+        })
+    }
+
+    pub fn store(&self, dir: &Path) -> io::Result<()> {
+        let file = PantryFile {
+            staples: self.staples.clone(),
+            items: self.items.iter().map(ToString::to_string).collect(),
+        };
+        let data = toml::to_string(&file).map_err(io::Error::other)?;
+        fs::write(dir.join(PANTRY_FILE_NAME), data)
+    }
+
+    pub fn items(&self) -> &[Ingredient] {
+        &self.items
+    }
+
+    /// Adds `item`, replacing an item of the same ingredient and kind.
+    pub fn add(&mut self, item: Ingredient, synonyms: &Synonyms) {
+        let id = synonyms.id(&item.name);
+        self.items
+            .retain(|other| synonyms.id(&other.name) != id || !same_kind(&other.kind, &item.kind));
+        self.items.push(item);
+        self.items
+            .sort_by_cached_key(|item| normalize(&item.to_string()));
+    }
+
+    /// Removes the items of the ingredient `item`, only those of its kind if
+    /// it has one. Returns whether there were any.
+    pub fn remove(&mut self, item: &Ingredient, synonyms: &Synonyms) -> bool {
+        let id = synonyms.id(&item.name);
+        let count = self.items.len();
+        self.items.retain(|other| {
+            synonyms.id(&other.name) != id
+                || item
+                    .kind
+                    .as_ref()
+                    .is_some_and(|_| !same_kind(&other.kind, &item.kind))
+        });
+        self.items.len() < count
+    }
+
+    /// Returns whether `ingredient` is a staple or an item of the pantry.
+    ///
+    /// Names match with their synonyms. Items without a kind cover
+    /// ingredients of any kind and the other way around. If both have
+    /// quantities of the same unit, there must be enough of the item.
+    pub fn covers(&self, ingredient: &Ingredient, synonyms: &Synonyms) -> bool {
+        let id = synonyms.id(&ingredient.name);
+        if self.staples.iter().any(|staple| synonyms.id(staple) == id) {
+            return true;
+        }
+        self.items.iter().any(|item| {
+            synonyms.id(&item.name) == id
+                && (item.kind.is_none()
+                    || ingredient.kind.is_none()
+                    || same_kind(&item.kind, &ingredient.kind))
+                && match (&item.quantity, &ingredient.quantity) {
+                    (Some(available), Some(needed)) => is_enough(available, needed),
+                    _ => true,
+                }
+        })
+    }
+}
+
+fn same_kind(a: &Option<String>, b: &Option<String>) -> bool {
+    a.as_deref().map(normalize) == b.as_deref().map(normalize)
+}
+
+/// Returns whether the `available` quantity is at least the `needed` one,
+/// quantities of different units can not be compared and are enough.
+fn is_enough(available: &Quantity, needed: &Quantity) -> bool {
+    if available.unit.as_deref().map(normalize) != needed.unit.as_deref().map(normalize) {
+        return true;
+    }
+    let (a_numer, a_denom) = available.value.ratio();
+    let (b_numer, b_denom) = needed.value.ratio();
+    u128::from(a_numer) * u128::from(b_denom) >= u128::from(b_numer) * u128::from(a_denom)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn ingredient(s: &str) -> Ingredient {
+        Ingredient::parse_from_str(s).unwrap()
+    }
+
+    #[test]
+    fn test_covers() {
+        let synonyms = Synonyms::new([("tomato".to_string(), vec!["tomatoes".to_string()])]);
+        let mut pantry = Pantry {
+            staples: vec!["Salt".into()],
+            ..Pantry::default()
+        };
+        pantry.add(ingredient("flour: 500 g"), &synonyms);
+        pantry.add(ingredient("Tomatoes, canned"), &synonyms);
+        pantry.add(ingredient("eggs"), &synonyms);
+        assert!(pantry.covers(&ingredient("salt: 1 pinch"), &synonyms));
+        assert!(pantry.covers(&ingredient("Flour: 1/2 g"), &synonyms));
+        assert!(pantry.covers(&ingredient("flour: 500 g"), &synonyms));
+        assert!(!pantry.covers(&ingredient("flour: 501 g"), &synonyms));
+        assert!(pantry.covers(&ingredient("flour: 2 cups"), &synonyms));
+        assert!(pantry.covers(&ingredient("tomato, canned: 2"), &synonyms));
+        assert!(pantry.covers(&ingredient("tomato"), &synonyms));
+        assert!(!pantry.covers(&ingredient("tomato, fresh"), &synonyms));
+        assert!(pantry.covers(&ingredient("eggs, large: 2"), &synonyms));
+        assert!(!pantry.covers(&ingredient("milk"), &synonyms));
+
+        // replace and remove items
+        pantry.add(ingredient("flour: 1 kg"), &synonyms);
+        assert_eq!(pantry.items().len(), 3);
+        assert!(!pantry.remove(&ingredient("tomato, fresh"), &synonyms));
+        assert!(pantry.remove(&ingredient("tomato"), &synonyms));
+        assert!(!pantry.remove(&ingredient("tomato"), &synonyms));
+        let items: Vec<_> = pantry.items().iter().map(ToString::to_string).collect();
+        assert_eq!(items, ["eggs", "flour: 1 kg"]);
+    }
+
+    #[test]
+    fn test_store_and_load() -> Result<()> {
+        let temp_dir = tempdir()?;
+        assert!(Pantry::load(temp_dir.path())?.items().is_empty());
+        let mut pantry = Pantry::default();
+        pantry.staples.push("salt".into());
+        pantry.add(ingredient("flour: 1 kg"), &Synonyms::default());
+        pantry.store(temp_dir.path())?;
+        let pantry = Pantry::load(temp_dir.path())?;
+        assert_eq!(pantry.staples, ["salt"]);
+        assert_eq!(pantry.items()[0].to_string(), "flour: 1 kg");
+        fs::write(temp_dir.path().join(PANTRY_FILE_NAME), "items = [\": 1\"]")?;
+        assert!(matches!(
+            Pantry::load(temp_dir.path()),
+            Err(Error::InvalidPantryItem(..))
+        ));
+        Ok(())
+    }
+}
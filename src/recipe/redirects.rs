@@ -0,0 +1,87 @@
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+
+pub const REDIRECTS_FILE_NAME: &str = ".redirects.toml";
+
+/// Log of renamed recipe directories (old name → new name).
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Redirects(BTreeMap<String, String>);
+
+impl Redirects {
+    pub fn load(recipe_dir: &Path) -> Result<Self> {
+        match fs::read_to_string(recipe_dir.join(REDIRECTS_FILE_NAME)) {
+            Ok(data) => toml::from_str(&data).map_err(Error::InvalidRedirectsFileFormat),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    pub fn store(&self, recipe_dir: &Path) -> io::Result<()> {
+        let data = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(recipe_dir.join(REDIRECTS_FILE_NAME), data)
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, from: S, to: S) {
+        let to = to.into();
+        // the new name exists again, so it must not redirect anymore
+        self.0.remove(&to);
+        self.0.insert(from.into(), to);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Follows the redirects of `name` to the current name.
+    pub fn resolve<'a>(&'a self, mut name: &'a str) -> Option<&'a str> {
+        let mut result = None;
+        // limit steps to not get stuck in cycles of a manually edited file
+        for _ in 0..self.0.len() {
+            match self.0.get(name) {
+                Some(next) => {
+                    name = next;
+                    result = Some(name);
+                }
+                None => break,
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut redirects = Redirects::default();
+        redirects.insert("a", "b");
+        redirects.insert("b", "c");
+        assert_eq!(redirects.resolve("a"), Some("c"));
+        assert_eq!(redirects.resolve("b"), Some("c"));
+        assert_eq!(redirects.resolve("c"), None);
+        redirects.insert("c", "a");
+        assert_eq!(redirects.resolve("b"), Some("a"));
+        assert_eq!(redirects.resolve("a"), None);
+        assert_eq!(redirects.names().collect::<Vec<_>>(), ["b", "c"]);
+    }
+
+    #[test]
+    fn test_store_and_load() -> Result<()> {
+        let temp_dir = tempdir()?;
+        assert!(Redirects::load(temp_dir.path())?.names().next().is_none());
+        let mut redirects = Redirects::default();
+        redirects.insert("old name", "new name");
+        redirects.store(temp_dir.path())?;
+        let redirects = Redirects::load(temp_dir.path())?;
+        assert_eq!(redirects.resolve("old name"), Some("new name"));
+        Ok(())
+    }
+}
@@ -0,0 +1,50 @@
+//! References to other recipes by title, e.g. `@[Béchamel]`, in ingredient
+//! names and instructions.
+
+use std::ops::Range;
+
+use super::errors::ParseResult;
+
+pub const REFERENCE_START: &str = "@[";
+
+/// Returns the byte range and the title of each reference in `text`.
+pub fn find_references(text: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let start = offset + text[offset..].find(REFERENCE_START)?;
+        let title_start = start + REFERENCE_START.len();
+        let end = title_start + text[title_start..].find(']')?;
+        offset = end + 1;
+        Some((start..offset, text[title_start..end].trim()))
+    })
+}
+
+/// Returns an error for references without closing bracket or title.
+pub(super) fn validate(text: &str) -> ParseResult<()> {
+    let mut offset = 0;
+    for (range, title) in find_references(text) {
+        if title.is_empty() {
+            return Err(format!("empty recipe reference in '{text}'").into());
+        }
+        offset = range.end;
+    }
+    if text[offset..].contains(REFERENCE_START) {
+        return Err(format!("missing closing bracket of recipe reference in '{text}'").into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_references() {
+        let text = "Pour the @[ Béchamel ] over the @[Ragù], not @[";
+        let references: Vec<_> = find_references(text).collect();
+        assert_eq!(references, [(9..23, "Béchamel"), (33..41, "Ragù")]);
+        assert!(validate("@[Béchamel]").is_ok());
+        assert!(validate("@[ ]").is_err());
+        assert!(validate(text).is_err());
+    }
+}
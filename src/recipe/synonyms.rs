@@ -0,0 +1,243 @@
+//! Canonical ingredient names, so that differently written names of the
+//! same ingredient (e.g. "tomatoes", "Tomato" and "Tomaten") match.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs, io,
+    path::Path,
+};
+
+use crate::error::{Error, Result};
+
+use super::reference::find_references;
+
+pub const SYNONYMS_FILE_NAME: &str = "synonyms.toml";
+
+/// Variants of ingredient names (e.g. translations) by their canonical name.
+///
+/// The synonyms file lists the variants of each canonical name:
+///
+/// ```toml
+/// tomato = ["Tomaten", "Paradeiser"]
+/// ```
+///
+/// Names match regardless of case, whitespace, simple English and German
+/// plural forms (see [`singular`]), a kind after a comma (`tomatoes,
+/// crushed`) and the markup of recipe references (`@[Title]`).
+#[derive(Debug, Default)]
+pub struct Synonyms {
+    /// Canonical names by the keys of their variants (and their own key)
+    canonical_names: BTreeMap<String, String>,
+    /// Variants as written by canonical name
+    variants: BTreeMap<String, Vec<String>>,
+}
+
+impl Synonyms {
+    /// Loads the synonyms file of `dir`, there are no synonyms without it.
+    pub fn load(dir: &Path) -> Result<Self> {
+        match fs::read_to_string(dir.join(SYNONYMS_FILE_NAME)) {
+            Ok(data) => {
+                let groups: BTreeMap<String, Vec<String>> =
+                    toml::from_str(&data).map_err(Error::InvalidSynonymsFileFormat)?;
+                Ok(Self::new(groups))
+            }
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    /// Creates synonyms from canonical names with their variants.
+    pub fn new<I, V>(groups: I) -> Self
+    where
+        I: IntoIterator<Item = (String, V)>,
+        V: IntoIterator<Item = String>,
+    {
+        let mut synonyms = Self::default();
+        for (canonical, variants) in groups {
+            let variants: Vec<_> = variants.into_iter().collect();
+            for variant in &variants {
+                synonyms
+                    .canonical_names
+                    .insert(key(variant), canonical.clone());
+            }
+            synonyms
+                .canonical_names
+                .insert(key(&canonical), canonical.clone());
+            synonyms.variants.insert(canonical, variants);
+        }
+        synonyms
+    }
+
+    /// Returns the canonical name of the ingredient `name` as written in the
+    /// synonyms file, if it has one.
+    pub fn canonical_name(&self, name: &str) -> Option<&str> {
+        self.canonical_names.get(&key(name)).map(String::as_str)
+    }
+
+    /// Returns the canonical name and all variants of the ingredient `name`
+    /// as written in the synonyms file, none if it has no canonical name.
+    pub fn variants(&self, name: &str) -> impl Iterator<Item = &str> {
+        self.canonical_name(name)
+            .and_then(|canonical| self.variants.get_key_value(canonical))
+            .into_iter()
+            .flat_map(|(canonical, variants)| {
+                std::iter::once(canonical.as_str()).chain(variants.iter().map(String::as_str))
+            })
+    }
+
+    /// Returns the identifier of the ingredient `name`, the key of its
+    /// canonical name or else of the name itself. Names with the same
+    /// identifier are the same ingredient.
+    pub fn id(&self, name: &str) -> String {
+        let key = key(name);
+        match self.canonical_names.get(&key) {
+            Some(canonical) => self::key(canonical),
+            None => key,
+        }
+    }
+
+    /// Returns the distinct `names` (by identifier) without a canonical
+    /// name, sorted by identifier. Names referencing recipes are skipped.
+    pub fn unmatched<'a, I>(&self, names: I) -> Vec<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut ids = HashSet::new();
+        let mut unmatched: Vec<_> = names
+            .into_iter()
+            .filter(|name| find_references(name).next().is_none())
+            .filter(|name| self.canonical_name(name).is_none())
+            .map(|name| (self.id(name), name))
+            .filter(|(id, _)| ids.insert(id.clone()))
+            .collect();
+        unmatched.sort_unstable();
+        unmatched.into_iter().map(|(_, name)| name).collect()
+    }
+}
+
+/// Returns `name` in lowercase with single spaces between words.
+pub fn normalize(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Returns the singular form of a lowercase English or German noun, or at
+/// least the same stem for its singular and plural forms (e.g. "chicke" for
+/// "chicken" and "chickens").
+///
+/// Irregular plural forms like "Äpfel" need a synonym.
+pub fn singular(word: &str) -> String {
+    let mut word = word.to_string();
+    loop {
+        let length = word.chars().count();
+        if length > 4 && word.ends_with("ies") {
+            word.replace_range(word.len() - 3.., "y");
+        } else if (length > 5 && word.ends_with("oes"))
+            || ["ches", "shes", "sses", "xes"]
+                .iter()
+                .any(|suffix| word.ends_with(suffix))
+        {
+            word.truncate(word.len() - 2);
+        } else if (length > 3
+            && word.ends_with('s')
+            && !["ss", "us", "is"]
+                .iter()
+                .any(|suffix| word.ends_with(suffix)))
+            || (length > 4
+                && ["en", "eln", "ern"]
+                    .iter()
+                    .any(|suffix| word.ends_with(suffix)))
+        {
+            word.pop();
+        } else {
+            return word;
+        }
+    }
+}
+
+/// Returns the normalized name without kind and reference markup, with the
+/// singular form of its last word.
+fn key(name: &str) -> String {
+    let name = name.split_once(", ").map_or(name, |(name, _)| name);
+    let mut text = String::new();
+    let mut offset = 0;
+    for (range, title) in find_references(name) {
+        text.push_str(&name[offset..range.start]);
+        text.push_str(title);
+        offset = range.end;
+    }
+    text.push_str(&name[offset..]);
+    let mut key = normalize(&text);
+    let start = key.rfind(' ').map_or(0, |index| index + 1);
+    let last = singular(&key[start..]);
+    key.replace_range(start.., &last);
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_singular() {
+        let words = [
+            ("tomatoes", "tomato"),
+            ("tomato", "tomato"),
+            ("berries", "berry"),
+            ("peaches", "peach"),
+            ("glasses", "glass"),
+            ("eggs", "egg"),
+            ("pies", "pie"),
+            ("shoes", "shoe"),
+            ("couscous", "couscous"),
+            ("tomaten", "tomate"),
+            ("zwiebeln", "zwiebel"),
+            ("eiern", "eier"),
+            ("mehl", "mehl"),
+        ];
+        for (word, expected) in words {
+            assert_eq!(singular(word), expected);
+        }
+        assert_eq!(singular("chickens"), singular("chicken"));
+    }
+
+    #[test]
+    fn test_synonyms() -> Result<()> {
+        let temp_dir = tempdir()?;
+        let synonyms = Synonyms::load(temp_dir.path())?;
+        assert_eq!(synonyms.id(" Olive  Oils"), "olive oil");
+        assert_eq!(synonyms.id("@[Cherry Tomatoes], halved"), "cherry tomato");
+        fs::write(
+            temp_dir.path().join(SYNONYMS_FILE_NAME),
+            "Tomato = [\"Tomaten\", \"Paradeiser\"]\n",
+        )?;
+        let synonyms = Synonyms::load(temp_dir.path())?;
+        for name in [
+            "tomato",
+            "Tomatoes",
+            "TOMATE",
+            "tomatoes, crushed",
+            "Paradeiser",
+        ] {
+            assert_eq!(synonyms.canonical_name(name), Some("Tomato"));
+            assert_eq!(synonyms.id(name), "tomato");
+        }
+        assert_eq!(
+            synonyms.variants("tomatoes").collect::<Vec<_>>(),
+            ["Tomato", "Tomaten", "Paradeiser"]
+        );
+        assert_eq!(synonyms.canonical_name("basil"), None);
+        assert_eq!(synonyms.variants("basil").count(), 0);
+        assert_eq!(
+            synonyms.unmatched(["Basil", "tomatoes", "basil", "@[Sauce]", "Onions"]),
+            ["Basil", "Onions"]
+        );
+        fs::write(temp_dir.path().join(SYNONYMS_FILE_NAME), "tomato = 1")?;
+        assert!(Synonyms::load(temp_dir.path()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use serde_json::Map;
+use tera::{Error, Result, Value, escape_html};
+
+use crate::{
+    recipe::{
+        image_ref::{find_image_refs, remove_image_refs},
+        markdown::{self, Inline},
+    },
+    util::escape_xml,
+};
+
+/// Tera filter sorting a list of recipes (or index entries) by the argument
+/// `by`: `title` (default), `time`, `yield` or `ingredients`.
+///
+/// With `reverse = true` the order is descending. Recipes without a time
+/// always come last.
+pub fn sort_recipes(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let Value::Array(items) = value else {
+        return Err(Error::msg("sort_recipes: value must be an array"));
+    };
+    let by = match args.get("by") {
+        Some(Value::String(by)) => by.as_str(),
+        Some(_) => return Err(Error::msg("sort_recipes: `by` must be a string")),
+        None => "title",
+    };
+    let reverse = args
+        .get("reverse")
+        .and_then(Value::as_bool)
+        .unwrap_or(false);
+    let mut items = items.clone();
+    match by {
+        "title" => {
+            items.sort_by_cached_key(|item| {
+                let title = recipe(item).get("title").and_then(Value::as_str);
+                title.unwrap_or_default().to_lowercase()
+            });
+            if reverse {
+                items.reverse();
+            }
+        }
+        "time" => sort_by_number(&mut items, minutes, reverse),
+        "yield" => sort_by_number(
+            &mut items,
+            |item| number(item, "/metadata/yield/value"),
+            reverse,
+        ),
+        "ingredients" => sort_by_number(
+            &mut items,
+            |item| number(item, "/ingredients/count"),
+            reverse,
+        ),
+        by => return Err(Error::msg(format!("sort_recipes: unknown sort key '{by}'"))),
+    }
+    Ok(Value::Array(items))
+}
+
+fn sort_by_number(items: &mut [Value], key: impl Fn(&Value) -> Option<u64>, reverse: bool) {
+    items.sort_by(|a, b| match (key(a), key(b)) {
+        (Some(a), Some(b)) if reverse => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    });
+}
+
+/// Index entries contain the recipe, plain recipes are used as is.
+fn recipe(item: &Value) -> &Value {
+    item.get("recipe").unwrap_or(item)
+}
+
+fn number(item: &Value, pointer: &str) -> Option<u64> {
+    recipe(item).pointer(pointer)?.as_u64()
+}
+
+fn minutes(item: &Value) -> Option<u64> {
+    Some(
+        number(item, "/metadata/duration/hours")? * 60
+            + number(item, "/metadata/duration/minutes")?,
+    )
+}
+
+/// Tera filter returning the images of the argument `gallery` that a text
+/// references with `@image(number)` (starting at 1) or `@image(file name)`.
+pub fn image_refs(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let text = text(value, "image_refs")?;
+    let gallery = match args.get("gallery") {
+        Some(Value::Array(gallery)) => gallery.as_slice(),
+        Some(_) => return Err(Error::msg("image_refs: `gallery` must be an array")),
+        None => &[],
+    };
+    find_image_refs(text)
+        .map(|(_, name)| {
+            let image = match name.parse::<usize>() {
+                Ok(number) => number.checked_sub(1).and_then(|index| gallery.get(index)),
+                Err(_) => gallery
+                    .iter()
+                    .find(|image| image.get("name").and_then(Value::as_str) == Some(name)),
+            };
+            image
+                .cloned()
+                .ok_or_else(|| Error::msg(format!("image_refs: unknown image '{name}'")))
+        })
+        .collect::<Result<_>>()
+        .map(Value::Array)
+}
+
+/// Tera filter removing all image references (see [`image_refs`]) from a text.
+pub fn strip_image_refs(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    text(value, "strip_image_refs").map(|text| remove_image_refs(text).into())
+}
+
+/// Tera filter rendering the inline Markdown of a text (see
+/// [`markdown`]) to HTML if `enabled`, otherwise like [`link_references`].
+/// Text is escaped if `escape` is set, so that the result can be marked as
+/// `safe`.
+pub fn inline_markdown(
+    value: &Value,
+    args: &HashMap<String, Value>,
+    escape: bool,
+    enabled: bool,
+) -> Result<Value> {
+    let text = text(value, "inline_markdown")?;
+    let inlines = if enabled {
+        markdown::parse(text)
+    } else {
+        markdown::parse_references(text)
+    };
+    Ok(HtmlWriter::new(args, escape, "inline_markdown")?
+        .write(&inlines)
+        .into())
+}
+
+/// Tera filter rendering recipe references (`@[Title]`) of a text as links
+/// to the URLs of the argument `references` (by title). References without
+/// URL are rendered as their title. Text is escaped if `escape` is set, so
+/// that the result can be marked as `safe`.
+pub fn link_references(
+    value: &Value,
+    args: &HashMap<String, Value>,
+    escape: bool,
+) -> Result<Value> {
+    let text = text(value, "link_references")?;
+    Ok(HtmlWriter::new(args, escape, "link_references")?
+        .write(&markdown::parse_references(text))
+        .into())
+}
+
+struct HtmlWriter<'a> {
+    escape: bool,
+    references: Option<&'a Map<String, Value>>,
+}
+
+impl<'a> HtmlWriter<'a> {
+    fn new(args: &'a HashMap<String, Value>, escape: bool, filter: &str) -> Result<Self> {
+        let references = match args.get("references") {
+            Some(Value::Object(references)) => Some(references),
+            Some(Value::Null) | None => None,
+            Some(_) => {
+                return Err(Error::msg(format!(
+                    "{filter}: `references` must be an object"
+                )));
+            }
+        };
+        Ok(Self { escape, references })
+    }
+
+    fn write(&self, inlines: &[Inline]) -> String {
+        let mut html = String::new();
+        self.write_into(inlines, &mut html);
+        html
+    }
+
+    fn escaped(&self, text: &str) -> String {
+        if self.escape {
+            escape_html(text)
+        } else {
+            text.into()
+        }
+    }
+
+    fn write_into(&self, inlines: &[Inline], html: &mut String) {
+        for inline in inlines {
+            match inline {
+                Inline::Text(text) => html.push_str(&self.escaped(text)),
+                Inline::Emphasis(content) => {
+                    html.push_str("<em>");
+                    self.write_into(content, html);
+                    html.push_str("</em>");
+                }
+                Inline::Strong(content) => {
+                    html.push_str("<strong>");
+                    self.write_into(content, html);
+                    html.push_str("</strong>");
+                }
+                Inline::Code(code) => {
+                    html.push_str("<code>");
+                    html.push_str(&self.escaped(code));
+                    html.push_str("</code>");
+                }
+                Inline::Link { content, url } if is_safe_url(url) => {
+                    html.push_str("<a href=\"");
+                    html.push_str(&escape_xml(url));
+                    html.push_str("\">");
+                    self.write_into(content, html);
+                    html.push_str("</a>");
+                }
+                Inline::Link { content, .. } => self.write_into(content, html),
+                Inline::Reference(title) => {
+                    let url = self
+                        .references
+                        .and_then(|references| references.get(title))
+                        .and_then(Value::as_str);
+                    if let Some(url) = url {
+                        html.push_str("<a class=\"reference\" href=\"");
+                        html.push_str(&escape_xml(url));
+                        html.push_str("\">");
+                        html.push_str(&self.escaped(title));
+                        html.push_str("</a>");
+                    } else {
+                        html.push_str(&self.escaped(title));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Whether a link URL is relative or uses a scheme that cannot run scripts.
+fn is_safe_url(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) if !scheme.contains(['/', '?', '#']) => {
+            ["http", "https", "mailto"].contains(&scheme.to_lowercase().as_str())
+        }
+        _ => true,
+    }
+}
+
+fn text<'a>(value: &'a Value, filter: &str) -> Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::msg(format!("{filter}: value must be a string")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn titles(value: &Value) -> Vec<&str> {
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| recipe(item)["title"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_recipes() -> Result<()> {
+        let recipes = json!([
+            {"recipe": {"title": "b", "metadata": {"duration": null, "yield": {"value": 2}}}},
+            {"recipe": {"title": "C", "metadata": {"duration": {"hours": 1, "minutes": 0}, "yield": {"value": 4}}}},
+            {"recipe": {"title": "a", "metadata": {"duration": {"hours": 0, "minutes": 30}, "yield": {"value": 1}}}},
+        ]);
+        let args = |by: &str, reverse: bool| {
+            HashMap::from([("by".into(), json!(by)), ("reverse".into(), json!(reverse))])
+        };
+        assert_eq!(
+            titles(&sort_recipes(&recipes, &HashMap::new())?),
+            ["a", "b", "C"]
+        );
+        assert_eq!(
+            titles(&sort_recipes(&recipes, &args("title", true))?),
+            ["C", "b", "a"]
+        );
+        assert_eq!(
+            titles(&sort_recipes(&recipes, &args("time", false))?),
+            ["a", "C", "b"]
+        );
+        assert_eq!(
+            titles(&sort_recipes(&recipes, &args("time", true))?),
+            ["C", "a", "b"]
+        );
+        assert_eq!(
+            titles(&sort_recipes(&recipes, &args("yield", false))?),
+            ["a", "b", "C"]
+        );
+        assert!(sort_recipes(&recipes, &args("color", false)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_refs() -> Result<()> {
+        let text = json!("Bake until golden @image(2) @image(b.jpg)");
+        let gallery = json!([{"name": "a.jpg"}, {"name": "b.jpg"}]);
+        let args = HashMap::from([("gallery".into(), gallery)]);
+        assert_eq!(
+            image_refs(&text, &args)?,
+            json!([{"name": "b.jpg"}, {"name": "b.jpg"}])
+        );
+        assert!(image_refs(&json!("@image(3)"), &args).is_err());
+        assert!(image_refs(&json!("@image(0)"), &args).is_err());
+        assert_eq!(image_refs(&json!("no images"), &args)?, json!([]));
+        assert_eq!(
+            strip_image_refs(&text, &HashMap::new())?,
+            json!("Bake until golden")
+        );
+        assert_eq!(
+            strip_image_refs(
+                &json!("@image(1) Bake @image(2) until golden"),
+                &HashMap::new()
+            )?,
+            json!("Bake until golden")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_inline_markdown() -> Result<()> {
+        let text = json!("Do **not** stir <b>it</b>, see [video](https://example.com/?a&b)");
+        assert_eq!(
+            inline_markdown(&text, &HashMap::new(), true, true)?,
+            json!(
+                "Do <strong>not</strong> stir &lt;b&gt;it&lt;&#x2F;b&gt;, see <a href=\"https://example.com/?a&amp;b\">video</a>"
+            )
+        );
+        assert_eq!(
+            inline_markdown(&text, &HashMap::new(), false, true)?,
+            json!(
+                "Do <strong>not</strong> stir <b>it</b>, see <a href=\"https://example.com/?a&amp;b\">video</a>"
+            )
+        );
+        assert_eq!(
+            inline_markdown(&json!("*a* <b>"), &HashMap::new(), true, false)?,
+            json!("*a* &lt;b&gt;")
+        );
+        assert_eq!(
+            inline_markdown(
+                &json!("[click](JavaScript:alert(1)) [a](a/b:c)"),
+                &HashMap::new(),
+                true,
+                true
+            )?,
+            json!("click <a href=\"a/b:c\">a</a>")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_link_references() -> Result<()> {
+        let args = HashMap::from([(
+            "references".into(),
+            json!({"Béchamel": "../recipes/bechamel.html"}),
+        )]);
+        let text = json!("Add *the* @[Béchamel] & @[Ragù]");
+        assert_eq!(
+            link_references(&text, &args, true)?,
+            json!(
+                "Add *the* <a class=\"reference\" href=\"../recipes/bechamel.html\">Béchamel</a> &amp; Ragù"
+            )
+        );
+        assert_eq!(
+            inline_markdown(&text, &args, true, true)?,
+            json!(
+                "Add <em>the</em> <a class=\"reference\" href=\"../recipes/bechamel.html\">Béchamel</a> &amp; Ragù"
+            )
+        );
+        assert!(
+            link_references(
+                &text,
+                &HashMap::from([("references".into(), json!(1))]),
+                true
+            )
+            .is_err()
+        );
+        Ok(())
+    }
+}
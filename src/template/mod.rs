@@ -0,0 +1,267 @@
+pub(crate) mod filters;
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tera::Tera;
+use toml::Value;
+
+use crate::{
+    error::{Error, Result},
+    generator::manifest::Hasher,
+    util::list_files,
+};
+
+pub type Context = tera::Context;
+
+pub const LANGUAGE_DIR: &str = "lang";
+pub const LANGUAGE_FILE_EXTENSION: &str = "toml";
+pub const STATIC_DIR: &str = "static";
+pub const TERA_DIR: &str = "tera";
+
+/// Directory within `TERA_DIR` with additional page templates of any file extension.
+pub const PAGES_DIR: &str = "pages";
+
+/// Single document with all recipes, rendered instead of the recipe pages.
+pub const COOKBOOK_NAME: &str = "cookbook";
+pub const INDEX_NAME: &str = "index";
+pub const INGREDIENTS_NAME: &str = "ingredients";
+pub const RECIPE_NAME: &str = "recipe";
+pub const REDIRECT_NAME: &str = "redirect";
+pub const TAG_NAME: &str = "tag";
+
+/// File name suffixes of escaped templates: markup formats, but not pages of
+/// other formats (e.g. JSON or plain text).
+const ESCAPED_SUFFIXES: [&str; 5] = [".html", ".htm", ".xhtml", ".xml", ".md"];
+
+pub struct Engine {
+    tera: Tera,
+    tera_path: PathBuf,
+    escape: bool,
+    inline_markdown: bool,
+    file_ext: String,
+    language_path: PathBuf,
+    static_path: PathBuf,
+    pub language: Option<String>,
+    pub forced_context: Option<Context>,
+}
+
+impl Engine {
+    /// # Panics
+    ///
+    /// Will panic if `PathBuf::to_str` returns `None`
+    pub fn new<E: Into<String>>(
+        path: &Path,
+        escape: bool,
+        file_ext: E,
+        language: Option<&str>,
+    ) -> Result<Self> {
+        let file_ext = file_ext.into();
+        let mut glob_path = path.join(TERA_DIR).join("**/*");
+        glob_path.set_extension(&file_ext);
+        let mut tera = Tera::new(glob_path.to_str().expect("invalid template path"))?;
+        let pages_path = path.join(TERA_DIR).join(PAGES_DIR);
+        if pages_path.exists() {
+            // files with the template file extension are already loaded by the glob
+            let files = list_files(&pages_path)?.into_iter().filter_map(|file| {
+                let is_loaded = file.extension().is_some_and(|ext| *ext == *file_ext);
+                let name = Path::new(PAGES_DIR)
+                    .join(&file)
+                    .to_str()?
+                    .replace('\\', "/");
+                (!is_loaded).then(|| (pages_path.join(file), Some(name)))
+            });
+            tera.add_template_files(files.collect::<Vec<_>>())?;
+        }
+        tera.register_filter("sort_recipes", filters::sort_recipes);
+        tera.register_filter("image_refs", filters::image_refs);
+        tera.register_filter("strip_image_refs", filters::strip_image_refs);
+        tera.register_filter(
+            "link_references",
+            move |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+                filters::link_references(value, args, escape)
+            },
+        );
+        if escape {
+            tera.autoescape_on(ESCAPED_SUFFIXES.to_vec());
+        } else {
+            tera.autoescape_on(Vec::new());
+        }
+        let mut engine = Self {
+            tera,
+            tera_path: path.join(TERA_DIR),
+            escape,
+            inline_markdown: false,
+            file_ext,
+            language_path: path.join(LANGUAGE_DIR),
+            static_path: path.join(STATIC_DIR),
+            language: language.map(Into::into),
+            forced_context: None,
+        };
+        engine.set_inline_markdown(false);
+        if !engine.has_template(COOKBOOK_NAME) {
+            engine.require_template(RECIPE_NAME)?;
+        }
+        Ok(engine)
+    }
+
+    /// Renders the inline Markdown of instructions and notes with the filter
+    /// `inline_markdown`, which otherwise only links recipe references.
+    pub fn set_inline_markdown(&mut self, enabled: bool) {
+        let escape = self.escape;
+        self.inline_markdown = enabled;
+        self.tera.register_filter(
+            "inline_markdown",
+            move |value: &tera::Value, args: &HashMap<String, tera::Value>| {
+                filters::inline_markdown(value, args, escape, enabled)
+            },
+        );
+    }
+
+    /// Feeds everything that affects the rendered output into `hasher`:
+    /// options, forced context and all template and language files.
+    ///
+    /// Static files are not included as they do not affect rendering.
+    pub(crate) fn hash_into(&self, hasher: &mut Hasher) -> Result<()> {
+        hasher.update([u8::from(self.escape), u8::from(self.inline_markdown)]);
+        hasher.update(&self.file_ext);
+        hasher.update(self.language.as_deref().unwrap_or_default());
+        if let Some(context) = &self.forced_context {
+            hasher.update(context.clone().into_json().to_string());
+        }
+        for path in [&self.tera_path, &self.language_path] {
+            if path.exists() {
+                hash_files(path, hasher)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn render_cookbook(&self, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render(COOKBOOK_NAME, context, writer)
+    }
+
+    pub(crate) fn has_index_template(&self) -> bool {
+        self.has_template(INDEX_NAME)
+    }
+
+    pub(crate) fn render_index(&self, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render(INDEX_NAME, context, writer)
+    }
+
+    pub(crate) fn has_ingredients_template(&self) -> bool {
+        self.has_template(INGREDIENTS_NAME)
+    }
+
+    pub(crate) fn render_ingredients(
+        &self,
+        context: Context,
+        writer: impl io::Write,
+    ) -> Result<()> {
+        self.render(INGREDIENTS_NAME, context, writer)
+    }
+
+    pub(crate) fn render_recipe(&self, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render(RECIPE_NAME, context, writer)
+    }
+
+    pub(crate) fn has_redirect_template(&self) -> bool {
+        self.has_template(REDIRECT_NAME)
+    }
+
+    pub(crate) fn render_redirect(&self, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render(REDIRECT_NAME, context, writer)
+    }
+
+    pub(crate) fn has_tag_template(&self) -> bool {
+        self.has_template(TAG_NAME)
+    }
+
+    pub(crate) fn render_tag(&self, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render(TAG_NAME, context, writer)
+    }
+
+    /// Returns the names of all templates within `PAGES_DIR`, sorted.
+    pub(crate) fn page_names(&self) -> Vec<&str> {
+        let prefix = format!("{PAGES_DIR}/");
+        let mut names: Vec<_> = self
+            .tera
+            .get_template_names()
+            .filter(|name| name.starts_with(&prefix))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    pub(crate) fn render_page(
+        &self,
+        name: &str,
+        context: Context,
+        writer: impl io::Write,
+    ) -> Result<()> {
+        self.render_file(name, context, writer)
+    }
+
+    pub(crate) fn static_path(&self) -> &Path {
+        &self.static_path
+    }
+
+    fn has_template(&self, name: &str) -> bool {
+        let path = self.template_path(name);
+        self.tera.get_template_names().any(|name| name == path)
+    }
+
+    /// Returns an error if the template `name` does not exist.
+    pub(crate) fn require_template(&self, name: &str) -> Result<()> {
+        if self.has_template(name) {
+            Ok(())
+        } else {
+            Err(Error::MissingTemplateFile(self.template_path(name)))
+        }
+    }
+
+    fn load_language_file(&self) -> Result<Option<Value>> {
+        if let Some(language) = &self.language {
+            let path = self
+                .language_path
+                .join(language)
+                .with_extension(LANGUAGE_FILE_EXTENSION);
+            match fs::read_to_string(path) {
+                Ok(data) => Ok(Some(toml::from_str(&data)?)),
+                Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+                Err(error) => Err(error.into()),
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn render(&self, template_name: &str, context: Context, writer: impl io::Write) -> Result<()> {
+        self.render_file(&self.template_path(template_name), context, writer)
+    }
+
+    fn render_file(&self, path: &str, mut context: Context, writer: impl io::Write) -> Result<()> {
+        if let Some(data) = self.load_language_file()? {
+            context.insert("lang", &data);
+        }
+        if let Some(fc) = &self.forced_context {
+            context.extend(fc.clone());
+        }
+        Ok(self.tera.render_to(path, &context, writer)?)
+    }
+
+    fn template_path(&self, template_name: &str) -> String {
+        format!("{}.{}", template_name, self.file_ext)
+    }
+}
+
+pub(crate) fn hash_files(path: &Path, hasher: &mut Hasher) -> io::Result<()> {
+    for file in list_files(path)? {
+        hasher.update(file.to_string_lossy().as_bytes());
+        hasher.update_file(&path.join(file))?;
+    }
+    Ok(())
+}
@@ -1,10 +1,12 @@
 (() => {
   const selectors = {
     favorites: "body > header > nav > .favorites",
-    ingredientQuantity: "main > .recipe > .ingredients .quantity > .value",
+    ingredientQuantity:
+      "main > .recipe > :is(.ingredients, .shopping-list) .quantity > .value",
     random: "main > .recipes > .list .random",
     recipe: "main > .recipes > .list > ul > li",
     recipeCount: "main > .recipes > .list > .count",
+    search: "main > .recipes > .search input",
     tag: ".tags ul li .tag",
     tagReset: ".tags ul li .reset",
     yield: "main .recipe > .metadata > .yield > .content > .value",
@@ -30,14 +32,17 @@
   class TaggedElement {
     constructor(element) {
       this.element = element;
+      this.path = element.dataset.path;
       this.tags = queryElementTags(element);
     }
   }
 
   class TagManager {
-    constructor() {
+    constructor(config) {
+      this.baseUrl = config.baseUrl;
       this.tags = new Map();
       this.tagged = [];
+      this.search = null;
     }
 
     addTagElement(name, element) {
@@ -55,7 +60,9 @@
       window.addEventListener("hashchange", () => this.updateFromUrlHash());
       for (const tag of this.tags.values()) {
         for (const element of tag.elements) {
-          element.addEventListener("click", () => {
+          element.addEventListener("click", (event) => {
+            // tags may link to tag pages for browsing without JavaScript
+            event.preventDefault();
             tag.toggle();
             this.updateUrlHash();
           });
@@ -66,15 +73,18 @@
       }
     }
 
+    // whether the element has all active tags and matches the search query
+    isVisible(tagged) {
+      const hasTags = [...this.tags]
+        .filter(([, tag]) => tag.isActive)
+        .every(([name]) => tagged.tags.has(name));
+      return hasTags && (this.search?.matches(tagged.path) ?? true);
+    }
+
     random() {
-      const activateTagNames = [...this.tags]
-        .filter(([name, tag]) => tag.isActive)
-        .map(([name]) => name);
       const elements = this.tagged
-        .filter((tagEl) =>
-          activateTagNames.every((name) => tagEl.tags.has(name))
-        )
-        .map((tagEl) => tagEl.element);
+        .filter((tagged) => this.isVisible(tagged))
+        .map((tagged) => tagged.element);
       return elements[Math.floor(Math.random() * elements.length)];
     }
 
@@ -84,14 +94,13 @@
       }
       let count = 0;
       for (const tagged of this.tagged) {
-        const show = Array.from(this.tags)
-          .filter(([, tag]) => tag.isActive)
-          .every(([name]) => tagged.tags.has(name));
+        const show = this.isVisible(tagged);
         tagged.element.classList.toggle("hidden", !show);
         if (show) {
           count++;
         }
       }
+      this.sort();
       const recipeCount = document.querySelector(selectors.recipeCount);
       if (recipeCount !== null) {
         recipeCount.querySelector(".value").textContent = count;
@@ -107,6 +116,18 @@
       this.updateUrlHash();
     }
 
+    // orders the elements by search relevance (keeping the original order without a query)
+    sort() {
+      if (this.search === null || this.tagged.length === 0) {
+        return;
+      }
+      const ordered = this.tagged
+        .map((tagged, index) => [tagged, this.search.score(tagged.path), index])
+        .sort(([, a, i], [, b, j]) => b - a || i - j)
+        .map(([tagged]) => tagged.element);
+      ordered[0].parentElement.append(...ordered);
+    }
+
     updateUrlHash() {
       const params = new URLSearchParams(location.hash.slice(1));
       if ([...this.tags.values()].some((tag) => tag.isActive)) {
@@ -125,9 +146,7 @@
       const hash = [...params].map((entry) => entry.join("=")).join("&");
       let path = location.pathname;
       if (isRecipePage()) {
-        let index = path.lastIndexOf("/");
-        index = path.lastIndexOf("/", index - 1);
-        path = path.slice(0, index + 1);
+        path = new URL(this.baseUrl + "/", location.href).pathname;
       }
       if (hash !== "") {
         path += "#" + hash;
@@ -184,6 +203,93 @@
     }
   }
 
+  // splits text into terms the same way as the search index generator does
+  function tokenize(text, stopwords) {
+    return text
+      .normalize("NFD")
+      .replace(/\p{M}/gu, "")
+      .toLowerCase()
+      .replaceAll("ß", "ss")
+      .split(/[^\p{L}\p{N}]+/u)
+      .filter((term) => [...term].length > 1 && !stopwords.has(term));
+  }
+
+  class Search {
+    constructor(input, onChange) {
+      this.input = input;
+      this.onChange = onChange;
+      this.index = null;
+      // relevance by recipe path, `null` without a query
+      this.scores = null;
+      input.addEventListener("focus", () => this.load(), { once: true });
+      input.addEventListener("input", () => this.update());
+    }
+
+    load() {
+      this.loading ??= fetch(this.input.dataset.indexUrl)
+        .then((response) => response.json())
+        .then((index) => {
+          this.index = {
+            recipes: index.recipes,
+            stopwords: new Set(index.stopwords),
+            terms: Object.entries(index.terms),
+          };
+        });
+      return this.loading;
+    }
+
+    matches(path) {
+      return this.scores === null || this.scores.has(path);
+    }
+
+    score(path) {
+      return this.scores?.get(path) ?? 0;
+    }
+
+    async update() {
+      await this.load();
+      const terms = tokenize(this.input.value, this.index.stopwords);
+      if (terms.length === 0) {
+        this.scores = null;
+      } else {
+        // every term must be the prefix of an indexed term, the relevance
+        // is the sum of the best fields (title, tag, ingredient, text)
+        let scores = null;
+        for (const term of terms) {
+          const termScores = new Map();
+          for (const [indexed, postings] of this.index.terms) {
+            if (indexed.startsWith(term)) {
+              for (const [recipe, fields] of postings) {
+                termScores.set(
+                  recipe,
+                  Math.max(termScores.get(recipe) ?? 0, fields)
+                );
+              }
+            }
+          }
+          if (scores === null) {
+            scores = termScores;
+          } else {
+            for (const [recipe, score] of scores) {
+              if (termScores.has(recipe)) {
+                scores.set(recipe, score + termScores.get(recipe));
+              } else {
+                scores.delete(recipe);
+              }
+            }
+          }
+        }
+        this.scores = new Map(
+          [...scores].map(([recipe, score]) => [
+            this.index.recipes[recipe].path,
+            score,
+          ])
+        );
+      }
+      this.onChange();
+    }
+  }
+
   // greatest common divisor
   function gcd(a, b) {
     while (b !== 0) {
@@ -370,7 +476,7 @@
     const config = Object.fromEntries(
       Object.entries(document.getElementById("config").dataset)
     );
-    const tags = new TagManager();
+    const tags = new TagManager(config);
     for (const [name, element] of queryTagElements(document)) {
       tags.addTagElement(name, element);
     }
@@ -380,12 +486,15 @@
     for (const element of recipes) {
       tags.addTaggedElement(element);
     }
+    const searchInput = document.querySelector(selectors.search);
+    if (searchInput !== null) {
+      tags.search = new Search(searchInput, () => tags.refresh());
+    }
     tags.addEventListeners();
     tags.updateFromUrlHash();
     if (isRecipesPage()) {
       document.querySelector(selectors.random).addEventListener("click", () => {
-        const href = tags.random().querySelector("a").getAttribute("href");
-        location.assign(location.pathname + href);
+        location.assign(tags.random().querySelector("a").href);
       });
     }
     if (isRecipePage()) {
@@ -1,45 +1,10 @@
 use std::{
-    collections::HashSet,
     ffi::{OsStr, OsString},
     fs, io,
     path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
-pub(crate) struct UniqueNameFinder {
-    names: HashSet<String>,
-    number_prefix: String,
-    number_suffix: String,
-}
-
-impl UniqueNameFinder {
-    pub fn new<P, S>(number_prefix: P, number_suffix: S) -> Self
-    where
-        P: Into<String>,
-        S: Into<String>,
-    {
-        Self {
-            names: HashSet::new(),
-            number_prefix: number_prefix.into(),
-            number_suffix: number_suffix.into(),
-        }
-    }
-
-    pub fn find<S: Into<String>>(&mut self, name: S) -> String {
-        let name = name.into();
-        if self.names.insert(name.clone()) {
-            return name;
-        }
-        let mut i = 2;
-        loop {
-            let name = format!("{}{}{}{}", name, self.number_prefix, i, self.number_suffix);
-            if self.names.insert(name.clone()) {
-                return name;
-            }
-            i += 1;
-        }
-    }
-}
-
 pub(crate) fn append_os_file_ext<P, E>(path: P, file_ext: E) -> OsString
 where
     P: AsRef<OsStr>,
@@ -76,6 +41,76 @@ pub fn copy_dir<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()
     Ok(())
 }
 
+/// Returns the paths of all files within `path` (recursively), relative to `path` and sorted.
+pub(crate) fn list_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut stack = vec![PathBuf::new()];
+    while let Some(relative_dir) = stack.pop() {
+        for entry in fs::read_dir(path.join(&relative_dir))? {
+            let entry = entry?;
+            let relative_path = relative_dir.join(entry.file_name());
+            if entry.file_type()?.is_dir() {
+                stack.push(relative_path);
+            } else {
+                files.push(relative_path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Escapes text for XML content and attribute values.
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Formats a time as UTC timestamp, e.g. `2024-03-01T12:00:00Z`.
+pub(crate) fn timestamp(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+    // civil date from days since 1970-01-01 (proleptic Gregorian calendar)
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Returns the start (UTC) of a date, dates before 1970 are clamped to 1970-01-01.
+pub(crate) fn date_to_time(year: u32, month: u32, day: u32) -> SystemTime {
+    // days since 1970-01-01 from civil date (inverse of `timestamp`)
+    let year = u64::from(year) - u64::from(month <= 2);
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let month_index = u64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * month_index + 2) / 5 + u64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).saturating_sub(719_468);
+    UNIX_EPOCH + Duration::from_secs(days * 86400)
+}
+
 pub fn sanitize_file_name(file_name: &str) -> String {
     use sanitize_filename::{Options, sanitize_with_options};
 
@@ -94,13 +129,36 @@ mod tests {
     use super::*;
 
     #[test]
-    fn unique_name_finder_test() {
-        let mut finder = UniqueNameFinder::new(" (", ")");
-        assert_eq!(finder.find("foo"), "foo");
-        assert_eq!(finder.find("foo"), "foo (2)");
-        assert_eq!(finder.find("foo (2)"), "foo (2) (2)");
-        assert_eq!(finder.find("bar (2)"), "bar (2)");
-        assert_eq!(finder.find("bar"), "bar");
-        assert_eq!(finder.find("bar"), "bar (3)");
+    fn list_files_test() -> io::Result<()> {
+        let temp_dir = tempfile::tempdir()?;
+        let path = temp_dir.path();
+        fs::create_dir_all(path.join("b/c"))?;
+        fs::write(path.join("b/c/d"), "")?;
+        fs::write(path.join("a"), "")?;
+        assert_eq!(
+            list_files(path)?,
+            [PathBuf::from("a"), PathBuf::from("b/c/d")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        let time = UNIX_EPOCH + Duration::from_secs(1_709_294_645);
+        assert_eq!(timestamp(time), "2024-03-01T12:04:05Z");
+        let time = UNIX_EPOCH + Duration::from_secs(951_782_400);
+        assert_eq!(timestamp(time), "2000-02-29T00:00:00Z");
+    }
+
+    #[test]
+    fn test_date_to_time() {
+        assert_eq!(date_to_time(1970, 1, 1), UNIX_EPOCH);
+        assert_eq!(timestamp(date_to_time(2000, 2, 29)), "2000-02-29T00:00:00Z");
+        assert_eq!(
+            timestamp(date_to_time(2024, 12, 31)),
+            "2024-12-31T00:00:00Z"
+        );
+        assert_eq!(date_to_time(1969, 12, 31), UNIX_EPOCH);
     }
 }